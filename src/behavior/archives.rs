@@ -0,0 +1,284 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{self, File},
+    io::{BufReader, Read},
+};
+
+use flate2::read::GzDecoder;
+use jiff::{civil, tz::TimeZone};
+use log::{info, warn};
+use tar::{Archive, EntryType};
+use zip::ZipArchive;
+
+use crate::behavior::cache::{FileMeta, FileTree};
+use crate::cli::error::BFFError;
+
+/// Separates the path of an archive from the path of a member inside it.
+pub const SEPARATOR: &str = "!/";
+
+/// Virtual paths of the files in an archive, with their metadata.
+type Members = Vec<(String, FileMeta)>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Zip,
+    Tar,
+    TarGz,
+    TarZst,
+}
+
+/// Whether `path` is an archive `--archives` looks into.
+pub fn is_archive(path: &str) -> bool {
+    kind(path).is_some()
+}
+
+fn kind(path: &str) -> Option<Kind> {
+    let lower = path.to_lowercase();
+    if lower.ends_with(".zip") {
+        Some(Kind::Zip)
+    } else if lower.ends_with(".tar") {
+        Some(Kind::Tar)
+    } else if lower.ends_with(".tar.gz") || lower.ends_with(".tgz") {
+        Some(Kind::TarGz)
+    } else if lower.ends_with(".tar.zst") || lower.ends_with(".tzst") {
+        Some(Kind::TarZst)
+    } else {
+        None
+    }
+}
+
+/// The archive a virtual path points into, if `meta` is the one of an archive member.
+pub fn archive_of<'p>(path: &'p str, meta: &FileMeta) -> Option<&'p str> {
+    meta.archive.and_then(|len| path.get(..len as usize))
+}
+
+/// Lists the files inside an archive, with their metadata from the archive headers.
+fn list_members(path: &str, kind: Kind) -> Result<Members, BFFError> {
+    info!("indexing archive: {path}");
+    let file = BufReader::new(File::open(path)?);
+
+    match kind {
+        Kind::Zip => list_zip(path, file),
+        Kind::Tar => list_tar(path, file),
+        Kind::TarGz => list_tar(path, GzDecoder::new(file)),
+        Kind::TarZst => list_tar(path, zstd::Decoder::with_buffer(file)?),
+    }
+}
+
+fn list_tar<R: Read>(path: &str, reader: R) -> Result<Members, BFFError> {
+    let mut members = vec![];
+
+    for entry in Archive::new(reader).entries()? {
+        let entry = entry?;
+        let header = entry.header();
+        if !matches!(
+            header.entry_type(),
+            EntryType::Regular | EntryType::Continuous
+        ) {
+            continue;
+        }
+
+        let name = entry.path()?.to_string_lossy().into_owned();
+        let meta = FileMeta {
+            mtime: header.mtime().map_or(0, |t| t as i64),
+            size: entry.size(),
+            mode: header.mode().unwrap_or_default(),
+            archive: None,
+        };
+        add_member(&mut members, path, &name, meta);
+    }
+
+    Ok(members)
+}
+
+fn list_zip<R: Read + std::io::Seek>(path: &str, reader: R) -> Result<Members, BFFError> {
+    let mut archive = ZipArchive::new(reader)?;
+    let mut members = vec![];
+
+    for i in 0..archive.len() {
+        let file = archive.by_index_raw(i)?;
+        if file.is_dir() {
+            continue;
+        }
+
+        let mtime = file
+            .last_modified()
+            .and_then(|t| {
+                civil::date(t.year() as i16, t.month() as i8, t.day() as i8)
+                    .at(t.hour() as i8, t.minute() as i8, t.second() as i8, 0)
+                    .to_zoned(TimeZone::system())
+                    .ok()
+            })
+            .map_or(0, |z| z.timestamp().as_second());
+        let meta = FileMeta {
+            mtime,
+            size: file.size(),
+            mode: file.unix_mode().unwrap_or_default(),
+            archive: None,
+        };
+        add_member(&mut members, path, file.name(), meta);
+    }
+
+    Ok(members)
+}
+
+/// The virtual path of a member, with `.`, `..` and leading or repeated slashes resolved.
+/// `None` when the name leads out of the archive.
+fn virtual_path(archive: &str, member: &str) -> Option<String> {
+    let mut parts = vec![];
+    for part in member.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
+            }
+            part => parts.push(part),
+        }
+    }
+
+    if parts.is_empty() {
+        return None;
+    }
+    Some(format!("{archive}{SEPARATOR}{}", parts.join("/")))
+}
+
+fn add_member(members: &mut Members, archive: &str, name: &str, meta: FileMeta) {
+    match virtual_path(archive, name) {
+        Some(path) => members.push((
+            path,
+            FileMeta {
+                archive: Some(archive.len() as u32),
+                ..meta
+            },
+        )),
+        None => warn!("skipping member leading out of {archive}: {name}"),
+    }
+}
+
+/// Adds the members of every archive in `tree` as virtual paths right after the archive.
+///
+/// Archives whose modification time didn't change since they were last indexed, either in
+/// `tree` itself or in `previous` (the old cache when the tree was rebuilt), are not read
+/// again. Returns whether the tree changed.
+pub fn index_archives(tree: &mut FileTree, previous: Option<&FileTree>) -> bool {
+    let mut known: HashMap<&str, (i64, Members)> = HashMap::new();
+    for old in previous.into_iter().chain([&*tree]) {
+        for (archive, mtime) in &old.archives {
+            known.insert(archive, (*mtime, vec![]));
+        }
+        for (path, meta) in old.files.iter().zip(old.meta.iter()) {
+            if let Some((_, members)) = archive_of(path, meta).and_then(|a| known.get_mut(a)) {
+                members.push((path.clone(), *meta));
+            }
+        }
+    }
+
+    let mut changed = false;
+    let mut files = vec![];
+    let mut meta = vec![];
+    let mut archives = BTreeMap::new();
+
+    for (i, path) in tree.files.iter().enumerate() {
+        let file_meta = tree.meta.get(i).copied().unwrap_or_default();
+        if file_meta.archive.is_some() {
+            continue;
+        }
+
+        files.push(path.clone());
+        meta.push(file_meta);
+
+        let Some(kind) = kind(path) else {
+            continue;
+        };
+
+        // the cached metadata may be stale when the archive was rewritten in place
+        let mtime =
+            fs::metadata(path).map_or(file_meta.mtime, |md| FileMeta::from_metadata(&md).mtime);
+
+        let members = match known.remove(path.as_str()) {
+            Some((indexed, members)) if indexed == mtime => members,
+            _ => {
+                changed = true;
+                list_members(path, kind).unwrap_or_else(|e| {
+                    warn!("unable to read archive {path}: {e}");
+                    vec![]
+                })
+            }
+        };
+
+        for (member, member_meta) in members {
+            files.push(member);
+            meta.push(member_meta);
+        }
+        archives.insert(path.clone(), mtime);
+    }
+
+    changed |= archives != tree.archives;
+
+    tree.files = files.into_boxed_slice();
+    tree.meta = meta.into_boxed_slice();
+    tree.archives = archives;
+
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archive_kinds() {
+        assert_eq!(kind("./backups/backup_2023.tar.gz"), Some(Kind::TarGz));
+        assert_eq!(kind("./backups/site.ZIP"), Some(Kind::Zip));
+        assert_eq!(kind("./logs.tar.zst"), Some(Kind::TarZst));
+        assert_eq!(kind("./notes.gz"), None);
+        assert_eq!(
+            virtual_path("./backup.tar", "./docs/report.docx").unwrap(),
+            "./backup.tar!/docs/report.docx"
+        );
+        assert_eq!(
+            virtual_path("./backup.tar", "/docs//old/../report.docx").unwrap(),
+            "./backup.tar!/docs/report.docx"
+        );
+        assert_eq!(virtual_path("./backup.tar", "docs/../../etc/passwd"), None);
+        assert_eq!(virtual_path("./backup.tar", "./"), None);
+    }
+
+    #[test]
+    fn unchanged_archives_are_not_reread() {
+        let member = "./gone.tar!/docs/report.docx".to_string();
+        let mut tree = FileTree {
+            files: Box::new(["./gone.tar".to_string(), member.clone()]),
+            meta: Box::new([
+                FileMeta {
+                    mtime: 42,
+                    ..Default::default()
+                },
+                FileMeta {
+                    archive: Some(10),
+                    ..Default::default()
+                },
+            ]),
+            archives: BTreeMap::from([("./gone.tar".to_string(), 42)]),
+        };
+        assert_eq!(archive_of(&member, &tree.meta[1]), Some("./gone.tar"));
+
+        // the archive doesn't exist, so its members can only come from the index
+        assert!(!index_archives(&mut tree, None));
+        assert_eq!(&tree.files[..], ["./gone.tar".to_string(), member]);
+    }
+
+    #[test]
+    fn files_named_like_members_are_kept() {
+        let file = "./a!/b.txt".to_string();
+        let mut tree = FileTree {
+            files: Box::new([file.clone()]),
+            meta: Box::new([FileMeta::default()]),
+            archives: BTreeMap::new(),
+        };
+
+        assert!(!index_archives(&mut tree, None));
+        assert_eq!(&tree.files[..], [file.as_str()]);
+        assert_eq!(archive_of(&file, &tree.meta[0]), None);
+    }
+}
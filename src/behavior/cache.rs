@@ -1,6 +1,8 @@
 use std::{
+    collections::BTreeMap,
     fs::{self, File},
     io::{BufRead, BufReader, Read, Write},
+    time::UNIX_EPOCH,
 };
 
 use jwalk::WalkDir;
@@ -8,6 +10,9 @@ use log::info;
 use rmp_serde::{decode, encode};
 use serde::{Deserialize, Serialize};
 
+use super::archives;
+use super::checksum::check_cache;
+use super::index::INDEX_FILE;
 use crate::{cli::error, config::schema};
 
 pub const CACHE_FILE: &str = ".cache.bff";
@@ -15,17 +20,73 @@ pub const CACHE_FILE: &str = ".cache.bff";
 #[derive(Default, Serialize, Deserialize, Debug)]
 pub(crate) struct FileTree {
     pub files: Box<[String]>,
+    /// Metadata of every file, in the same order as `files`.
+    #[serde(default)]
+    pub meta: Box<[FileMeta]>,
+    /// Archives whose members are listed in `files`, with their mtime when they were read.
+    #[serde(default)]
+    pub archives: BTreeMap<String, i64>,
+}
+
+/// What goes into the file tree, recorded in the cache file so a cache built with
+/// different options gets rebuilt.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TreeOptions {
+    pub show_hidden: bool,
+    pub archives: bool,
+}
+
+impl TreeOptions {
+    pub fn flags(&self) -> String {
+        let mut flags = String::from(if self.show_hidden { "+" } else { "-" });
+        if self.archives {
+            flags.push('a');
+        }
+        flags
+    }
+}
+
+#[derive(Default, Serialize, Deserialize, Debug, Clone, Copy)]
+pub(crate) struct FileMeta {
+    /// Last modification time, in seconds since the epoch.
+    pub mtime: i64,
+    /// Size in bytes.
+    pub size: u64,
+    /// Unix permission bits, 0 on other platforms.
+    pub mode: u32,
+    /// Length of the archive's path, for the virtual path of a member inside an archive.
+    pub archive: Option<u32>,
+}
+
+impl FileMeta {
+    pub fn from_metadata(md: &fs::Metadata) -> Self {
+        #[cfg(unix)]
+        let mode = std::os::unix::fs::PermissionsExt::mode(&md.permissions());
+        #[cfg(not(unix))]
+        let mode = 0;
+
+        FileMeta {
+            mtime: md
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |d| d.as_secs() as i64),
+            size: md.len(),
+            mode,
+            archive: None,
+        }
+    }
 }
 
 pub fn write_cache_file(
     checksum: &str,
     file_tree: &FileTree,
-    mincache: bool,
+    tree_opts: &TreeOptions,
 ) -> Result<(), error::BFFError> {
     info!("writing to cache file");
 
     let mut file = File::create(CACHE_FILE)?;
-    writeln!(file, "{checksum}\n{}", if mincache { "-" } else { "+" })?;
+    writeln!(file, "{checksum}\n{}", tree_opts.flags())?;
 
     let buf = encode::to_vec(&file_tree)?;
     file.write_all(&buf)?;
@@ -55,24 +116,61 @@ pub fn read_cache_file() -> Result<FileTree, error::BFFError> {
 pub fn get_file_tree(show_hidden_dirs: bool) -> Result<FileTree, error::BFFError> {
     info!("building file tree");
 
+    let (files, meta): (Vec<String>, Vec<FileMeta>) = WalkDir::new(".")
+        .skip_hidden(!show_hidden_dirs)
+        .into_iter()
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            if entry.file_type().is_file() {
+                let displayed = entry.path().display().to_string();
+                info!("adding path to file tree: {displayed}");
+                let meta = entry
+                    .metadata()
+                    .map(|md| FileMeta::from_metadata(&md))
+                    .unwrap_or_default();
+                Some((displayed, meta))
+            } else {
+                None
+            }
+        })
+        .unzip();
+
     Ok(FileTree {
-        files: WalkDir::new(".")
-            .skip_hidden(!show_hidden_dirs)
-            .into_iter()
-            .filter_map(|entry| {
-                let entry = entry.ok()?;
-                if entry.file_type().is_file() {
-                    let displayed = entry.path().display().to_string();
-                    info!("adding path to file tree: {displayed}");
-                    Some(displayed)
-                } else {
-                    None
-                }
-            })
-            .collect::<Box<[String]>>(),
+        files: files.into_boxed_slice(),
+        meta: meta.into_boxed_slice(),
+        ..Default::default()
     })
 }
 
+/// Reads the file tree from the cache file, or rebuilds it when the cache is out of date
+/// or was built with other options. Returns whether the tree changed since the cache was
+/// written, the cache file is updated unless `skip_cache` is set.
+pub fn load_file_tree(
+    checksum: &str,
+    skip_cache: bool,
+    tree_opts: &TreeOptions,
+) -> Result<(FileTree, bool), error::BFFError> {
+    let (mut tree, mut changed, previous) = if !check_cache(checksum, tree_opts)? {
+        info!("cache is out of date");
+        // the old cache still knows the members of unchanged archives
+        let previous = tree_opts.archives.then(|| read_cache_file().ok()).flatten();
+        (get_file_tree(tree_opts.show_hidden)?, true, previous)
+    } else {
+        (read_cache_file()?, false, None)
+    };
+
+    if tree_opts.archives {
+        changed |= archives::index_archives(&mut tree, previous.as_ref());
+    }
+
+    if changed && !skip_cache {
+        info!("file tree changed, writing cache file");
+        write_cache_file(checksum, &tree, tree_opts)?
+    }
+
+    Ok((tree, changed))
+}
+
 pub fn clean() -> Result<(), error::BFFError> {
     info!("cleaning files");
 
@@ -81,6 +179,11 @@ pub fn clean() -> Result<(), error::BFFError> {
         fs::remove_file(CACHE_FILE)?;
     }
 
+    if fs::exists(INDEX_FILE)? {
+        info!("cleaning index file");
+        fs::remove_file(INDEX_FILE)?;
+    }
+
     if fs::exists(schema::CONFIG_FILE)? {
         info!("cleaning config file");
         fs::remove_file(schema::CONFIG_FILE)?;
@@ -6,10 +6,11 @@ use hex;
 use log::info;
 use sha2::{Digest, Sha256};
 
-use super::cache::CACHE_FILE;
+use super::cache::{TreeOptions, CACHE_FILE};
 use crate::cli::error::{self, BFFError};
 
-const EMPTY_HASH: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+/// Bumped whenever the layout of `FileTree` changes, so old cache files get rebuilt.
+const CACHE_VERSION: u8 = 4;
 
 pub fn gen_checksum() -> Result<String, error::BFFError> {
     info!("generating new checksum from file tree");
@@ -17,13 +18,17 @@ pub fn gen_checksum() -> Result<String, error::BFFError> {
     let metadata = std::fs::metadata(".")?;
     let modified = metadata.modified()?;
 
-    let hash = Sha256::digest(system_time_as_bytes(modified).unwrap_or_default());
-    let hex_string = hex::encode(hash);
-
-    if hex_string == EMPTY_HASH {
+    let bytes = system_time_as_bytes(modified).unwrap_or_default();
+    if bytes.is_empty() {
         return Err(error::BFFError::NoBytes);
     }
 
+    let hash = Sha256::new()
+        .chain_update(&bytes)
+        .chain_update([CACHE_VERSION])
+        .finalize();
+    let hex_string = hex::encode(hash);
+
     Ok(hex_string)
 }
 
@@ -45,7 +50,7 @@ pub fn read_checksum() -> Result<String, error::BFFError> {
     Ok(String::new())
 }
 
-pub fn check_cache(check: &str, mincache: bool) -> Result<bool, BFFError> {
+pub fn check_cache(check: &str, tree_opts: &TreeOptions) -> Result<bool, BFFError> {
     let checksum_matches = check == read_checksum()?;
 
     if std::fs::exists(CACHE_FILE)? {
@@ -60,12 +65,10 @@ pub fn check_cache(check: &str, mincache: bool) -> Result<bool, BFFError> {
             None => return Ok(false),
         };
 
-        let expected_flag = if mincache { "-" } else { "+" };
-
-        let flag_matches = expected_flag == second_line.trim_end();
+        let flag_matches = tree_opts.flags() == second_line.trim_end();
 
         if !flag_matches {
-            info!("mincache flag in cache file doesn't match, rebuilding cache")
+            info!("flags in cache file don't match, rebuilding cache")
         }
 
         Ok(checksum_matches && flag_matches)
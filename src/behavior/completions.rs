@@ -0,0 +1,114 @@
+use std::io;
+
+use clap::{Command, CommandFactory, ValueEnum};
+use clap_complete::{
+    engine::{ArgValueCandidates, CompletionCandidate},
+    env::{CompleteEnv, Shells},
+};
+use log::info;
+
+use crate::cli::arg_parser::{BFFArgs, CompletionsArgs};
+use crate::cli::error::BFFError;
+use crate::config::{config_reader::read_config, schema::TreeConfig};
+
+/// The variable telling `bff` to complete the command line instead of running it.
+const COMPLETE_VAR: &str = "COMPLETE";
+
+/// Answers the completion request of the shell and exits, if this run is one.
+pub fn complete() {
+    CompleteEnv::with_factory(command)
+        .var(COMPLETE_VAR)
+        .complete();
+}
+
+/// The command line of bff, completing aliases as search terms and as the alias to remove.
+fn command() -> Command {
+    let aliases = |arg: clap::Arg| arg.add(ArgValueCandidates::new(alias_candidates));
+    let search = |cmd: Command| cmd.mut_arg("terms", aliases);
+
+    BFFArgs::command()
+        .mut_subcommand("search", search)
+        .mut_subcommand("pick", search)
+        .mut_subcommand("open", search)
+        .mut_subcommand("alias", |cmd| {
+            cmd.mut_subcommand("remove", |cmd| cmd.mut_arg("name", aliases))
+        })
+}
+
+/// The aliases of the config in the working directory.
+fn alias_candidates() -> Vec<CompletionCandidate> {
+    read_config()
+        .map(|conf| candidates(&conf))
+        .unwrap_or_default()
+}
+
+fn candidates(conf: &TreeConfig) -> Vec<CompletionCandidate> {
+    let mut aliases: Vec<_> = conf.alias.iter().collect();
+    aliases.sort();
+
+    aliases
+        .into_iter()
+        .map(|(alias, expansion)| {
+            CompletionCandidate::new(alias).help(Some(expansion.clone().into()))
+        })
+        .collect()
+}
+
+/// Prints the script registering completions with the shell. Completing calls back into
+/// `bff`, so the aliases of the directory you are in are always up to date.
+pub fn run_completions(args: CompletionsArgs) -> Result<(), BFFError> {
+    let name = args
+        .shell
+        .to_possible_value()
+        .map_or(String::new(), |v| v.get_name().into());
+    let shells = Shells::builtins();
+    let Some(shell) = shells.completer(&name) else {
+        unreachable!("clap_complete supports every shell bff does");
+    };
+
+    info!("writing {name} completions");
+    let bin = env!("CARGO_PKG_NAME");
+    shell.write_registration(COMPLETE_VAR, bin, bin, bin, &mut io::stdout())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[test]
+    fn aliases_are_candidates() {
+        let conf = TreeConfig {
+            alias: HashMap::from([
+                ("stubs".to_string(), "payment checkstubs".to_string()),
+                ("week1".to_string(), "7".to_string()),
+            ]),
+            ..Default::default()
+        };
+
+        let found: Vec<_> = candidates(&conf)
+            .iter()
+            .map(|c| {
+                (
+                    c.get_value().to_string_lossy().into_owned(),
+                    c.get_help().map(ToString::to_string),
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("stubs".to_string(), Some("payment checkstubs".to_string())),
+                ("week1".to_string(), Some("7".to_string()))
+            ]
+        );
+    }
+
+    #[test]
+    fn completed_arguments_exist() {
+        // mut_subcommand and mut_arg panic on names that don't exist
+        command().debug_assert();
+    }
+}
@@ -0,0 +1,237 @@
+use std::{env, fs};
+
+use log::info;
+use toml::{Table, Value};
+
+use crate::behavior::exec;
+use crate::behavior::theme::Palette;
+use crate::cli::arg_parser::{AliasArgs, AliasCommands, ConfigArgs, ConfigCommands};
+use crate::cli::error::BFFError::{self, NotSet};
+use crate::config::config_reader::{load_config, MergedConfig};
+use crate::config::config_writer::{
+    config_path, dotted, init, parse_key, parse_value, validate, ConfigFile,
+};
+use crate::config::schema::TreeConfig;
+
+/// Every setting of `table` by dotted key, as in `alias.stubs`.
+fn settings<'t>(table: &'t Table, prefix: &str, out: &mut Vec<(String, &'t Value)>) {
+    for (key, value) in table {
+        let dotted = format!("{prefix}{key}");
+        match value {
+            Value::Table(t) => settings(t, &format!("{dotted}."), out),
+            _ => out.push((dotted, value)),
+        }
+    }
+}
+
+/// Prints the merged settings, with `--show-origin` the file each one comes from.
+fn show(show_origin: bool) -> Result<(), BFFError> {
+    let merged = load_config()?;
+
+    let mut all = vec![];
+    settings(&merged.table, "", &mut all);
+
+    for (key, value) in all {
+        match merged.origins.get(&key).filter(|_| show_origin) {
+            Some(origin) => println!("{}\t{key} = {value}", origin.display()),
+            None => println!("{key} = {value}"),
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints a merged setting, strings without quotes so scripts can use them as they are.
+fn get(key: &str) -> Result<(), BFFError> {
+    let path = parse_key(key)?;
+    let merged = load_config()?;
+
+    let mut value = None;
+    let mut table = Some(&merged.table);
+    for part in &path {
+        value = table.and_then(|t| t.get(part.get()));
+        table = value.and_then(Value::as_table);
+    }
+
+    match value {
+        Some(Value::String(s)) => println!("{s}"),
+        Some(value) => println!("{value}"),
+        None => return Err(NotSet(dotted(&path))),
+    }
+    Ok(())
+}
+
+/// Checks the styles of the theme, which can't be told apart from any other string before
+/// they are parsed.
+fn check_theme(conf: TreeConfig) -> Result<(), BFFError> {
+    Palette::new(&conf.theme).map(drop)
+}
+
+/// Writes `file` once its settings are known to be usable.
+fn save(file: &ConfigFile) -> Result<(), BFFError> {
+    check_theme(file.config()?)?;
+    file.save()
+}
+
+/// Sets a setting in `file`. Values that don't fit the schema as TOML are tried as strings,
+/// `file` is left as it was when neither fits.
+fn set(file: &mut ConfigFile, key: &str, raw: &str) -> Result<(), BFFError> {
+    let path = parse_key(key)?;
+    let original = file.clone();
+
+    let value = parse_value(raw);
+    let is_str = value.is_str();
+    let res = match file.set(&path, value).and_then(|_| save(file)) {
+        // `set alias.year 2024` means the string
+        Err(e) if !is_str => file
+            .set(&path, raw.into())
+            .and_then(|_| save(file))
+            .map_err(|_| e),
+        res => res,
+    };
+
+    if res.is_err() {
+        *file = original;
+    }
+    res
+}
+
+fn unset(key: &str, global: bool) -> Result<(), BFFError> {
+    let path = parse_key(key)?;
+    let mut file = ConfigFile::open(config_path(global)?)?;
+    file.unset(&path)?;
+    save(&file)
+}
+
+/// Opens the config file in the editor, and checks it once the editor exits.
+fn edit(global: bool) -> Result<(), BFFError> {
+    let path = config_path(global)?;
+    if !fs::exists(&path)? {
+        init(&path)?;
+    }
+    let before = fs::read_to_string(&path)?;
+
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .ok()
+        .filter(|e| !e.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string());
+    let mut line: Vec<String> = editor.split_whitespace().map(str::to_string).collect();
+    line.push(path.display().to_string());
+    info!("editing config with {line:?}");
+
+    exec::run(&line)?;
+    check_theme(validate(&path, &fs::read_to_string(&path)?, &before)?)
+}
+
+/// Runs `bff config`, which shows the merged settings when no subcommand is given.
+pub fn run_config(args: ConfigArgs) -> Result<(), BFFError> {
+    match args.cmd {
+        None => show(args.show_origin),
+        Some(ConfigCommands::Show { show_origin }) => show(show_origin),
+        Some(ConfigCommands::Init(target)) => init(&config_path(target.global)?),
+        Some(ConfigCommands::Get { key }) => get(&key),
+        Some(ConfigCommands::Set { key, value, target }) => set(
+            &mut ConfigFile::open(config_path(target.global)?)?,
+            &key,
+            &value,
+        ),
+        Some(ConfigCommands::Unset { key, target }) => unset(&key, target.global),
+        Some(ConfigCommands::Edit(target)) => edit(target.global),
+    }
+}
+
+fn add_alias(file: &mut ConfigFile, name: &str, expansion: &[String]) -> Result<(), BFFError> {
+    file.set(&alias_key(name), expansion.join(" ").into())?;
+    save(file)
+}
+
+fn remove_alias(file: &mut ConfigFile, name: &str) -> Result<(), BFFError> {
+    file.unset(&alias_key(name))?;
+    save(file)
+}
+
+/// The merged aliases as printed by `bff alias list`.
+fn alias_lines(merged: &MergedConfig, show_origin: bool) -> Vec<String> {
+    let Some(Value::Table(aliases)) = merged.table.get("alias") else {
+        return vec![];
+    };
+
+    aliases
+        .iter()
+        .map(|(name, expansion)| {
+            let origin = merged.origins.get(&format!("alias.{name}"));
+            match origin.filter(|_| show_origin) {
+                Some(origin) => format!("{}\t{name} = {expansion}", origin.display()),
+                None => format!("{name} = {expansion}"),
+            }
+        })
+        .collect()
+}
+
+pub fn run_alias(args: AliasArgs) -> Result<(), BFFError> {
+    match args.cmd {
+        AliasCommands::Add {
+            name,
+            expansion,
+            target,
+        } => add_alias(
+            &mut ConfigFile::open(config_path(target.global)?)?,
+            &name,
+            &expansion,
+        ),
+        AliasCommands::Remove { name, target } => {
+            remove_alias(&mut ConfigFile::open(config_path(target.global)?)?, &name)
+        }
+        AliasCommands::List { show_origin } => {
+            for line in alias_lines(&load_config()?, show_origin) {
+                println!("{line}");
+            }
+            Ok(())
+        }
+    }
+}
+
+fn alias_key(name: &str) -> Vec<toml_edit::Key> {
+    vec![toml_edit::Key::new("alias"), toml_edit::Key::new(name)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::config_reader::{find_layers, merge};
+
+    #[test]
+    fn settings_and_aliases_are_written() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let path = dir.join(".bff.toml");
+        let mut file = ConfigFile::open(path.clone()).unwrap();
+
+        set(&mut file, "search.count", "20").unwrap();
+        // not a number, and not a string either for an alias
+        set(&mut file, "alias.year", "2024").unwrap();
+        assert!(set(&mut file, "search.count", "many").is_err());
+        assert!(set(&mut file, "serach.count", "20").is_err());
+        assert!(set(&mut file, "theme.directroy", "blue").is_err());
+        assert!(set(&mut file, "theme.directory", "blurple").is_err());
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "[search]\ncount = 20\n\n[alias]\nyear = \"2024\"\n"
+        );
+
+        let mut file = ConfigFile::open(path.clone()).unwrap();
+        add_alias(&mut file, "stubs", &["payment".into(), "checkstubs".into()]).unwrap();
+        remove_alias(&mut file, "year").unwrap();
+        assert!(remove_alias(&mut file, "year").is_err());
+
+        let merged = merge(&find_layers(dir, None).unwrap());
+        assert_eq!(
+            alias_lines(&merged, true),
+            [format!(
+                "{}\tstubs = \"payment checkstubs\"",
+                path.display()
+            )]
+        );
+    }
+}
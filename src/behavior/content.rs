@@ -0,0 +1,249 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+};
+
+use ignore::{
+    gitignore::{Gitignore, GitignoreBuilder},
+    Match,
+};
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::behavior::strings::TermRange;
+use crate::options::MatchOptions;
+use crate::parser::normalize::Normalized;
+use crate::parser::term::Term;
+
+/// Only this many bytes are checked when deciding if a file is binary.
+const BINARY_PROBE: usize = 8192;
+
+/// Snippets longer than this are cut off.
+const MAX_SNIPPET: usize = 200;
+
+/// The first line of a file matching a `--content` term.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContentMatch {
+    /// 1-based line number.
+    pub line: usize,
+    pub snippet: String,
+    /// Number of content terms found anywhere in the file.
+    pub matched: usize,
+    /// Byte ranges of `snippet` matched by the terms, for highlighting.
+    pub ranges: Vec<TermRange>,
+}
+
+/// Looks for the content terms inside a file, line by line.
+///
+/// Returns `None` for binary or unreadable files and files that match none of the terms.
+pub fn search_content(path: &str, terms: &[Term], opts: &MatchOptions) -> Option<ContentMatch> {
+    let file = File::open(path).ok()?;
+    let mut reader = BufReader::new(file);
+
+    let head = reader.fill_buf().ok()?;
+    if head[..head.len().min(BINARY_PROBE)].contains(&0) {
+        info!("skipping binary file: {path}");
+        return None;
+    }
+
+    let mut found = vec![false; terms.len()];
+    let mut first: Option<(usize, String)> = None;
+    let mut buf = Vec::new();
+    let mut n = 0;
+
+    loop {
+        buf.clear();
+        if reader.read_until(b'\n', &mut buf).ok()? == 0 {
+            break;
+        }
+        n += 1;
+
+        let line = String::from_utf8_lossy(&buf);
+        let text = Normalized::new(&line, opts);
+
+        let mut any = false;
+        for (term, f) in terms.iter().zip(found.iter_mut()) {
+            if term.is_match(&text) {
+                *f = true;
+                any = true;
+            }
+        }
+
+        if any && first.is_none() {
+            first = Some((n, snippet(&line)));
+        }
+
+        if found.iter().all(|f| *f) {
+            break;
+        }
+    }
+
+    let (line, snippet) = first?;
+    Some(ContentMatch {
+        line,
+        snippet,
+        matched: found.iter().filter(|f| **f).count(),
+        ranges: vec![],
+    })
+}
+
+fn snippet(line: &str) -> String {
+    let line = line.trim();
+    match line.char_indices().nth(MAX_SNIPPET) {
+        Some((cut, _)) => format!("{}…", &line[..cut]),
+        None => line.to_string(),
+    }
+}
+
+/// `.gitignore` and `.ignore` files of every directory a path goes through.
+#[derive(Default)]
+pub struct IgnoreRules {
+    dirs: HashMap<PathBuf, Gitignore>,
+}
+
+impl IgnoreRules {
+    /// Checks ignore files from the deepest directory up, the first one with an opinion wins.
+    pub fn is_ignored(&mut self, path: &str) -> bool {
+        let path = Path::new(path);
+
+        for dir in path.ancestors().skip(1) {
+            if dir.as_os_str().is_empty() {
+                continue;
+            }
+
+            let rules = self
+                .dirs
+                .entry(dir.to_path_buf())
+                .or_insert_with(|| load_rules(dir));
+
+            match rules.matched_path_or_any_parents(path, false) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => {}
+            }
+        }
+
+        false
+    }
+}
+
+fn load_rules(dir: &Path) -> Gitignore {
+    let mut builder = GitignoreBuilder::new(dir);
+    for name in [".gitignore", ".ignore"] {
+        let file = dir.join(name);
+        if file.is_file() {
+            builder.add(file);
+        }
+    }
+    builder.build().unwrap_or_else(|_| Gitignore::empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::behavior::cache::FileTree;
+    use crate::behavior::filters::Filters;
+    use crate::behavior::search::rank_in_tree;
+    use crate::parser::term::Query;
+
+    fn path(dir: &Path, name: &str) -> String {
+        dir.join(name).to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn long_snippets_are_cut() {
+        let long = "x".repeat(300);
+        let s = snippet(&format!("   {long}\n"));
+        assert_eq!(s.chars().count(), MAX_SNIPPET + 1);
+        assert!(s.ends_with('…'));
+        assert_eq!(snippet("\tlet x = 1;\r\n"), "let x = 1;");
+    }
+
+    #[test]
+    fn first_matching_line_is_reported() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        fs::write(
+            dir.join("notes.txt"),
+            "groceries\nbuy milk\nmilk and eggs\n",
+        )
+        .unwrap();
+        fs::write(dir.join("notes.bin"), b"milk\0eggs\n").unwrap();
+
+        let opts = MatchOptions::default();
+        let terms = [
+            Term::fuzzy("milk"),
+            Term::fuzzy("eggs"),
+            Term::fuzzy("bread"),
+        ];
+
+        let found = search_content(&path(dir, "notes.txt"), &terms, &opts).unwrap();
+        assert_eq!(found.line, 2);
+        assert_eq!(found.snippet, "buy milk");
+        assert_eq!(found.matched, 2);
+
+        assert!(search_content(&path(dir, "notes.bin"), &terms, &opts).is_none());
+        assert!(search_content(&path(dir, "notes.txt"), &terms[2..], &opts).is_none());
+    }
+
+    #[test]
+    fn ignore_files_are_honored() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        fs::create_dir_all(dir.join("logs")).unwrap();
+        fs::write(dir.join(".gitignore"), "*.log\n").unwrap();
+        fs::write(dir.join("logs/.ignore"), "!keep.log\n").unwrap();
+
+        let mut rules = IgnoreRules::default();
+        assert!(rules.is_ignored(&path(dir, "error.log")));
+        assert!(rules.is_ignored(&path(dir, "logs/error.log")));
+        assert!(!rules.is_ignored(&path(dir, "logs/keep.log")));
+        assert!(!rules.is_ignored(&path(dir, "notes.txt")));
+    }
+
+    #[test]
+    fn content_search_ranks_and_highlights() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        fs::write(dir.join(".gitignore"), "ignored.txt\n").unwrap();
+        fs::write(dir.join("milk.txt"), "buy milk\n").unwrap();
+        fs::write(dir.join("both.txt"), "# list\nmilk and eggs\n").unwrap();
+        fs::write(dir.join("ignored.txt"), "milk and eggs\n").unwrap();
+        fs::write(dir.join("none.txt"), "bread\n").unwrap();
+
+        let tree = FileTree {
+            files: ["milk.txt", "both.txt", "ignored.txt", "none.txt"]
+                .iter()
+                .map(|name| path(dir, name))
+                .collect(),
+            ..Default::default()
+        };
+        let query = Query {
+            terms: vec![],
+            content: vec![Term::fuzzy("eggs"), Term::fuzzy("milk")],
+        };
+        let opts = MatchOptions::default();
+
+        let hits =
+            rank_in_tree(&tree, None, &query, false, 10, &opts, &Filters::default()).unwrap();
+        let found: Vec<(String, usize, Vec<TermRange>)> = hits
+            .into_iter()
+            .map(|h| {
+                let c = h.content.unwrap();
+                (h.path, c.line, c.ranges)
+            })
+            .collect();
+
+        assert_eq!(
+            found,
+            vec![
+                (path(dir, "both.txt"), 2, vec![(0, 4, 1), (9, 13, 0)]),
+                (path(dir, "milk.txt"), 1, vec![(4, 8, 1)]),
+            ]
+        );
+    }
+}
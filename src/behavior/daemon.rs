@@ -0,0 +1,528 @@
+use std::{
+    collections::HashMap,
+    env, fs,
+    io::{self, Read, Write},
+    mem,
+    os::unix::{
+        fs::{DirBuilderExt, MetadataExt},
+        net::{UnixListener, UnixStream},
+    },
+    path::{Path, PathBuf},
+    process,
+    sync::{Arc, PoisonError, RwLock},
+    thread,
+    time::Duration,
+};
+
+use log::{info, warn};
+use nix::unistd::{geteuid, Uid};
+use rmp_serde::{decode, encode};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use signal_hook::{
+    consts::{SIGHUP, SIGINT, SIGTERM},
+    iterator::Signals,
+};
+
+use crate::behavior::archives::{self, is_archive};
+use crate::behavior::cache::{self, FileMeta, FileTree, TreeOptions};
+use crate::behavior::filters::Filters;
+use crate::behavior::search::{rank_in_tree, Hit};
+use crate::behavior::watch::{self, Change, Events};
+use crate::cli::arg_parser::DaemonArgs;
+use crate::cli::error::BFFError::{self, DaemonError, NoResult};
+use crate::options::MatchOptions;
+use crate::parser::term::{parse_terms, Query};
+
+/// How long a search waits for the daemon to answer before giving up on it.
+const QUERY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How long the daemon waits for a search to send its request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Largest frames read from the socket, so a bogus length can't make either side
+/// allocate gigabytes. Requests only hold terms and options, answers can list many files.
+const MAX_REQUEST: usize = 1 << 20;
+const MAX_RESPONSE: usize = 1 << 30;
+
+/// A search, sent by `bff search` with the terms as typed (after alias expansion) since
+/// regexes and globs can't be sent compiled.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Request {
+    pub terms: Vec<String>,
+    pub regex: Vec<String>,
+    pub glob: Vec<String>,
+    pub content: Vec<String>,
+    pub strict: bool,
+    pub count: u32,
+    pub opts: MatchOptions,
+    pub filters: Filters,
+    pub tree_opts: TreeOptions,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum Response {
+    Hits(Vec<Hit>),
+    NoResult,
+    /// The daemon keeps a tree built with other options.
+    Unsupported,
+    Error(String),
+}
+
+/// The directory of the sockets, in the XDG runtime directory when there is one and in a
+/// directory named after the user in the temporary directory otherwise.
+fn socket_dir() -> PathBuf {
+    match dirs::runtime_dir() {
+        Some(dir) => dir.join(env!("CARGO_PKG_NAME")),
+        None => env::temp_dir().join(format!("{}-{}", env!("CARGO_PKG_NAME"), geteuid())),
+    }
+}
+
+/// Checks that only the current user can reach the sockets in `dir`, otherwise another
+/// user could pose as the daemon or read the file listings.
+fn check_private(dir: &Path) -> Result<(), BFFError> {
+    let md = fs::symlink_metadata(dir)?;
+    if !md.is_dir() || Uid::from_raw(md.uid()) != geteuid() || md.mode() & 0o077 != 0 {
+        return Err(DaemonError(format!(
+            "{} must be a directory only accessible to you",
+            dir.display()
+        )));
+    }
+    Ok(())
+}
+
+/// The socket of the daemon for `root`. Only searches run in `root` itself use it, the
+/// paths the daemon knows are relative to `root` and not to a subdirectory.
+fn socket_path(dir: &Path, root: &Path) -> PathBuf {
+    let hash = hex::encode(Sha256::digest(root.as_os_str().as_encoded_bytes()));
+    dir.join(format!("{}.sock", &hash[..16]))
+}
+
+/// The user running the process on the other end of `stream`.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn peer_uid(stream: &UnixStream) -> io::Result<Uid> {
+    use nix::sys::socket::{getsockopt, sockopt::PeerCredentials};
+
+    let cred = getsockopt(stream, PeerCredentials)?;
+    Ok(Uid::from_raw(cred.uid()))
+}
+
+/// The user running the process on the other end of `stream`.
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn peer_uid(stream: &UnixStream) -> io::Result<Uid> {
+    let (uid, _) = nix::unistd::getpeereid(stream)?;
+    Ok(uid)
+}
+
+/// Whether the other end of `stream` is run by the current user.
+fn same_user(stream: &UnixStream) -> bool {
+    match peer_uid(stream) {
+        Ok(uid) => uid == geteuid(),
+        Err(e) => {
+            warn!("unable to check who is on the other end of the socket: {e}");
+            false
+        }
+    }
+}
+
+fn write_frame<T: Serialize>(stream: &mut UnixStream, value: &T) -> Result<(), BFFError> {
+    let buf = encode::to_vec(value)?;
+    stream.write_all(&(buf.len() as u32).to_le_bytes())?;
+    stream.write_all(&buf)?;
+    Ok(())
+}
+
+fn read_frame<T: DeserializeOwned>(stream: &mut UnixStream, max: usize) -> Result<T, BFFError> {
+    let mut len = [0; 4];
+    stream.read_exact(&mut len)?;
+
+    let len = u32::from_le_bytes(len) as usize;
+    if len > max {
+        return Err(DaemonError(format!(
+            "message of {len} bytes is over the limit of {max}"
+        )));
+    }
+
+    let mut buf = vec![0; len];
+    stream.read_exact(&mut buf)?;
+
+    Ok(decode::from_slice(&buf)?)
+}
+
+/// Asks the daemon running for the working directory, if there is one. `None` means the
+/// search has to be done without it.
+pub fn query(request: &Request) -> Result<Option<Vec<Hit>>, BFFError> {
+    let dir = socket_dir();
+    if !fs::exists(&dir)? {
+        return Ok(None);
+    }
+    if let Err(e) = check_private(&dir) {
+        warn!("searching without the daemon: {e}");
+        return Ok(None);
+    }
+
+    let socket = socket_path(&dir, &env::current_dir()?.canonicalize()?);
+    let Ok(mut stream) = UnixStream::connect(&socket) else {
+        info!("no daemon listening on {}", socket.display());
+        return Ok(None);
+    };
+    if !same_user(&stream) {
+        warn!(
+            "{} is not run by you, searching without it",
+            socket.display()
+        );
+        return Ok(None);
+    }
+
+    info!("asking daemon on {}", socket.display());
+    let response = stream
+        .set_read_timeout(Some(QUERY_TIMEOUT))
+        .map_err(BFFError::from)
+        .and_then(|_| write_frame(&mut stream, request))
+        .and_then(|_| read_frame(&mut stream, MAX_RESPONSE));
+
+    match response {
+        Ok(Response::Hits(hits)) => Ok(Some(hits)),
+        Ok(Response::NoResult) => Err(NoResult),
+        Ok(Response::Unsupported) => {
+            info!("daemon was started with other options, searching without it");
+            Ok(None)
+        }
+        Ok(Response::Error(e)) => Err(DaemonError(e)),
+        Err(e) => {
+            warn!("unable to talk to the daemon, searching without it: {e}");
+            Ok(None)
+        }
+    }
+}
+
+/// The file tree of the daemon, kept up to date with filesystem events.
+struct LiveTree {
+    tree: FileTree,
+    tree_opts: TreeOptions,
+    /// Index of every path in `tree.files`.
+    positions: HashMap<String, usize>,
+}
+
+impl LiveTree {
+    fn new(mut tree: FileTree, tree_opts: TreeOptions) -> Self {
+        // caches from before metadata was stored have none
+        if tree.meta.len() != tree.files.len() {
+            let mut meta = mem::take(&mut tree.meta).into_vec();
+            meta.resize(tree.files.len(), FileMeta::default());
+            tree.meta = meta.into_boxed_slice();
+        }
+
+        let mut live = LiveTree {
+            tree,
+            tree_opts,
+            positions: HashMap::new(),
+        };
+        live.reindex();
+        live
+    }
+
+    fn reindex(&mut self) {
+        self.positions = self
+            .tree
+            .files
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (p.clone(), i))
+            .collect();
+    }
+
+    /// Brings the tree up to date with the changed paths, relative to `root`.
+    fn apply(&mut self, changed: &[PathBuf], root: &Path) {
+        let mut files = mem::take(&mut self.tree.files).into_vec();
+        let mut meta = mem::take(&mut self.tree.meta).into_vec();
+        let mut archive_changed = false;
+
+        for change in watch::changes(changed, root, self.tree_opts.show_hidden) {
+            match change {
+                Change::Added(path, file_meta) => {
+                    archive_changed |= is_archive(&path);
+                    self.upsert(&mut files, &mut meta, path, file_meta);
+                }
+                Change::Removed(path) => {
+                    archive_changed |= is_archive(&path);
+                    self.remove(&mut files, &mut meta, &path);
+                }
+            }
+        }
+
+        self.tree.files = files.into_boxed_slice();
+        self.tree.meta = meta.into_boxed_slice();
+
+        if self.tree_opts.archives && archive_changed {
+            archives::index_archives(&mut self.tree, None);
+            self.reindex();
+        }
+    }
+
+    fn upsert(
+        &mut self,
+        files: &mut Vec<String>,
+        meta: &mut Vec<FileMeta>,
+        path: String,
+        file_meta: FileMeta,
+    ) {
+        match self.positions.get(&path) {
+            Some(&i) => meta[i] = file_meta,
+            None => {
+                info!("adding path to file tree: {path}");
+                self.positions.insert(path.clone(), files.len());
+                files.push(path);
+                meta.push(file_meta);
+            }
+        }
+    }
+
+    /// Removes a file, or everything inside a directory or an archive.
+    fn remove(&mut self, files: &mut Vec<String>, meta: &mut Vec<FileMeta>, path: &str) {
+        let dir = format!("{path}/");
+        let archive = Some(path.len() as u32);
+
+        let mut i = 0;
+        while i < files.len() {
+            let f = &files[i];
+            let member = meta[i].archive == archive && f.starts_with(path);
+            if f != path && !f.starts_with(&dir) && !member {
+                i += 1;
+                continue;
+            }
+
+            info!("removing path from file tree: {f}");
+            self.positions.remove(f);
+            files.swap_remove(i);
+            meta.swap_remove(i);
+            if let Some(moved) = files.get(i) {
+                self.positions.insert(moved.clone(), i);
+            }
+        }
+    }
+
+    fn answer(&self, request: Request) -> Response {
+        if request.tree_opts != self.tree_opts {
+            return Response::Unsupported;
+        }
+
+        let query = parse_terms(&request.terms, &request.regex, &request.glob, &request.opts)
+            .and_then(|terms| {
+                Ok(Query {
+                    terms,
+                    content: parse_terms(&request.content, &[], &[], &request.opts)?,
+                })
+            });
+
+        let hits = query.and_then(|query| {
+            rank_in_tree(
+                &self.tree,
+                None,
+                &query,
+                request.strict,
+                request.count,
+                &request.opts,
+                &request.filters,
+            )
+        });
+
+        match hits {
+            Ok(hits) => Response::Hits(hits),
+            Err(NoResult) => Response::NoResult,
+            Err(e) => Response::Error(e.to_string()),
+        }
+    }
+}
+
+fn follow(rx: Events, root: &Path, live: &RwLock<LiveTree>) {
+    while let Some(changed) = watch::next_batch(&rx) {
+        info!("applying {} changed paths", changed.len());
+        live.write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .apply(&changed, root);
+    }
+}
+
+fn serve(mut stream: UnixStream, live: &RwLock<LiveTree>) -> Result<(), BFFError> {
+    // a search that never sends its request, or never reads the answer, can't hold a
+    // thread forever
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    stream.set_write_timeout(Some(QUERY_TIMEOUT))?;
+
+    let request: Request = read_frame(&mut stream, MAX_REQUEST)?;
+    info!("answering request: {request:?}");
+
+    let response = live
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .answer(request);
+    write_frame(&mut stream, &response)
+}
+
+fn remove_socket(socket: &Path) {
+    if let Err(e) = fs::remove_file(socket) {
+        warn!("unable to remove {}: {e}", socket.display());
+    }
+}
+
+/// Removes the socket when the daemon stops on an error.
+struct SocketFile(PathBuf);
+
+impl Drop for SocketFile {
+    fn drop(&mut self) {
+        remove_socket(&self.0);
+    }
+}
+
+/// Watches the working directory and answers searches over a Unix socket until killed.
+pub fn run_daemon(args: DaemonArgs) -> Result<(), BFFError> {
+    let tree_opts = TreeOptions {
+        show_hidden: args.show_hidden,
+        archives: args.archives,
+    };
+
+    let root = env::current_dir()?.canonicalize()?;
+    let dir = socket_dir();
+    fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(&dir)?;
+    check_private(&dir)?;
+
+    let socket = socket_path(&dir, &root);
+    if UnixStream::connect(&socket).is_ok() {
+        return Err(DaemonError(format!(
+            "a daemon is already running for {}",
+            root.display()
+        )));
+    }
+
+    // left over by a daemon that didn't exit cleanly
+    if fs::exists(&socket)? {
+        fs::remove_file(&socket)?;
+    }
+
+    // watching starts before the tree is read so no change is missed in between
+    let (_watcher, rx) = watch::watch(&root)?;
+
+    // the cache file can't tell if a subdirectory changed, so the tree is walked afresh
+    let mut tree = cache::get_file_tree(tree_opts.show_hidden)?;
+    if tree_opts.archives {
+        archives::index_archives(&mut tree, None);
+    }
+    info!("watching {} files in {}", tree.files.len(), root.display());
+
+    let live = Arc::new(RwLock::new(LiveTree::new(tree, tree_opts)));
+    {
+        let live = Arc::clone(&live);
+        let root = root.clone();
+        thread::spawn(move || follow(rx, &root, &live));
+    }
+
+    let mut signals = Signals::new([SIGINT, SIGTERM, SIGHUP])?;
+    let listener = UnixListener::bind(&socket)?;
+    let _socket = SocketFile(socket.clone());
+    {
+        let socket = socket.clone();
+        thread::spawn(move || {
+            if let Some(signal) = signals.forever().next() {
+                info!("exiting on signal {signal}");
+                remove_socket(&socket);
+                process::exit(128 + signal);
+            }
+        });
+    }
+    info!("listening on {}", socket.display());
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(s) => s,
+            Err(e) => {
+                warn!("unable to accept connection: {e}");
+                continue;
+            }
+        };
+
+        if !same_user(&stream) {
+            warn!("refusing a connection from another user");
+            continue;
+        }
+
+        let live = Arc::clone(&live);
+        thread::spawn(move || {
+            if let Err(e) = serve(stream, &live) {
+                warn!("unable to answer request: {e}");
+            }
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn live_tree_follows_changes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        fs::create_dir(root.join("docs")).unwrap();
+        let mut live = LiveTree::new(
+            FileTree {
+                files: Box::new(["./docs/old.txt".to_string(), "./keep.txt".to_string()]),
+                meta: Box::new([FileMeta::default(); 2]),
+                ..Default::default()
+            },
+            TreeOptions::default(),
+        );
+
+        fs::write(root.join("docs/new.txt"), "").unwrap();
+        fs::write(root.join(".hidden"), "").unwrap();
+        live.apply(
+            &[
+                root.join("docs"),
+                root.join(".hidden"),
+                root.join("docs/old.txt"),
+            ],
+            root,
+        );
+
+        let mut files = live.tree.files.to_vec();
+        files.sort();
+        assert_eq!(files, ["./docs/new.txt", "./keep.txt"]);
+        assert_eq!(live.positions.len(), 2);
+    }
+
+    #[test]
+    fn sockets_stay_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o700)).unwrap();
+        assert!(check_private(dir).is_ok());
+
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o755)).unwrap();
+        assert!(check_private(dir).is_err());
+
+        let (a, b) = UnixStream::pair().unwrap();
+        assert!(same_user(&a) && same_user(&b));
+    }
+
+    #[test]
+    fn oversized_frames_are_refused() {
+        let (mut a, mut b) = UnixStream::pair().unwrap();
+
+        // the length is checked before anything is allocated
+        a.write_all(&u32::MAX.to_le_bytes()).unwrap();
+        assert!(read_frame::<Request>(&mut b, MAX_REQUEST).is_err());
+
+        write_frame(&mut a, &Response::NoResult).unwrap();
+        assert!(matches!(
+            read_frame(&mut b, MAX_RESPONSE),
+            Ok(Response::NoResult)
+        ));
+    }
+}
@@ -0,0 +1,241 @@
+use std::{
+    io::{self, Write},
+    path::Path,
+    process::{Command, ExitStatus},
+};
+
+use log::info;
+use rayon::{prelude::*, ThreadPoolBuilder};
+
+use crate::cli::error::BFFError::{self, CommandFailed, ExecError};
+
+/// Placeholders, longest first so `{/}` isn't taken for `{}` followed by text.
+const PLACEHOLDERS: [&str; 5] = ["{//}", "{/.}", "{/}", "{.}", "{}"];
+
+/// A command line from `--exec` or `--exec-batch`, with placeholders for the result.
+#[derive(Debug)]
+pub struct CommandTemplate<'a> {
+    args: &'a [String],
+}
+
+impl<'a> CommandTemplate<'a> {
+    pub fn new(args: &'a [String]) -> Self {
+        CommandTemplate { args }
+    }
+
+    fn has_placeholder(&self) -> bool {
+        self.args
+            .iter()
+            .any(|a| PLACEHOLDERS.iter().any(|p| a.contains(p)))
+    }
+
+    /// The command line for one result, the path is appended when there is no placeholder.
+    pub fn expand(&self, path: &str) -> Vec<String> {
+        let mut line: Vec<String> = self.args.iter().map(|a| substitute(a, path)).collect();
+        if !self.has_placeholder() {
+            line.push(path.to_string());
+        }
+        line
+    }
+
+    /// The command line for all results, arguments with a placeholder are repeated for
+    /// each of them.
+    fn expand_batch(&self, paths: &[&str]) -> Vec<String> {
+        let mut line = vec![];
+        for arg in self.args {
+            if PLACEHOLDERS.iter().any(|p| arg.contains(p)) {
+                line.extend(paths.iter().map(|path| substitute(arg, path)));
+            } else {
+                line.push(arg.clone());
+            }
+        }
+        if !self.has_placeholder() {
+            line.extend(paths.iter().map(|p| p.to_string()));
+        }
+        line
+    }
+}
+
+fn substitute(arg: &str, path: &str) -> String {
+    let p = Path::new(path);
+    let basename = p.file_name().map_or(path.into(), |n| n.to_string_lossy());
+    let parent = match p.parent().map(|d| d.to_string_lossy()) {
+        Some(d) if !d.is_empty() => d,
+        _ => ".".into(),
+    };
+    let stem = p
+        .file_stem()
+        .map_or(basename.clone(), |n| n.to_string_lossy());
+    let no_ext = p.with_extension("");
+
+    let mut out = String::new();
+    let mut rest = arg;
+    while let Some(i) = rest.find('{') {
+        out.push_str(&rest[..i]);
+        rest = &rest[i..];
+
+        let Some(placeholder) = PLACEHOLDERS.iter().find(|p| rest.starts_with(*p)) else {
+            out.push('{');
+            rest = &rest[1..];
+            continue;
+        };
+        match *placeholder {
+            "{//}" => out.push_str(&parent),
+            "{/.}" => out.push_str(&stem),
+            "{/}" => out.push_str(&basename),
+            "{.}" => out.push_str(&no_ext.to_string_lossy()),
+            _ => out.push_str(path),
+        }
+        rest = &rest[placeholder.len()..];
+    }
+    out.push_str(rest);
+    out
+}
+
+fn command(line: &[String]) -> Command {
+    info!("running: {line:?}");
+    let mut cmd = Command::new(&line[0]);
+    cmd.args(&line[1..]);
+    cmd
+}
+
+fn exit_code(status: ExitStatus) -> i32 {
+    // killed by a signal
+    status.code().unwrap_or(1)
+}
+
+/// Runs the command once for each path, `threads` at a time. Fails with the exit code of
+/// the first failing command, in the order of the results.
+///
+/// Only a single thread hands the terminal to the command, commands running in parallel
+/// read from `/dev/null` and have their output held back.
+pub fn exec_each(
+    template: &CommandTemplate,
+    paths: &[&str],
+    threads: Option<usize>,
+) -> Result<(), BFFError> {
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(threads.unwrap_or(0))
+        .build()
+        .map_err(|e| ExecError(e.to_string()))?;
+    let parallel = pool.current_num_threads() > 1;
+
+    let codes: Vec<Result<i32, BFFError>> = pool.install(|| {
+        paths
+            .par_iter()
+            .map(|path| {
+                let line = template.expand(path);
+                let mut cmd = command(&line);
+
+                if !parallel {
+                    let status = cmd
+                        .status()
+                        .map_err(|e| ExecError(format!("{}: {e}", line[0])))?;
+                    return Ok(exit_code(status));
+                }
+
+                // output is held back until the command is done so commands don't interleave
+                let output = cmd
+                    .output()
+                    .map_err(|e| ExecError(format!("{}: {e}", line[0])))?;
+                io::stdout().lock().write_all(&output.stdout)?;
+                io::stderr().lock().write_all(&output.stderr)?;
+                Ok(exit_code(output.status))
+            })
+            .collect()
+    });
+
+    for code in codes {
+        match code? {
+            0 => {}
+            code => return Err(CommandFailed(code)),
+        }
+    }
+    Ok(())
+}
+
+/// Runs the command once with all paths.
+pub fn exec_batch(template: &CommandTemplate, paths: &[&str]) -> Result<(), BFFError> {
+    if paths.is_empty() {
+        return Ok(());
+    }
+
+    run(&template.expand_batch(paths))
+}
+
+/// Runs a command line in the foreground and waits for it to exit.
+pub fn run(line: &[String]) -> Result<(), BFFError> {
+    let status = command(line)
+        .status()
+        .map_err(|e| ExecError(format!("{}: {e}", line[0])))?;
+
+    match exit_code(status) {
+        0 => Ok(()),
+        code => Err(CommandFailed(code)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split(' ').map(str::to_string).collect()
+    }
+
+    #[test]
+    fn placeholders_expand() {
+        let line = args("convert {} {//}/{/.}.png {/} {.}.bak {x}");
+        assert_eq!(
+            CommandTemplate::new(&line).expand("./docs/report.final.jpg"),
+            [
+                "convert",
+                "./docs/report.final.jpg",
+                "./docs/report.final.png",
+                "report.final.jpg",
+                "./docs/report.final.bak",
+                "{x}"
+            ]
+        );
+
+        let line = args("rm -v");
+        assert_eq!(
+            CommandTemplate::new(&line).expand("notes.txt"),
+            ["rm", "-v", "notes.txt"]
+        );
+        assert_eq!(
+            CommandTemplate::new(&line).expand_batch(&["a", "b"]),
+            ["rm", "-v", "a", "b"]
+        );
+
+        let line = args("tar czf out.tgz {/}");
+        assert_eq!(
+            CommandTemplate::new(&line).expand_batch(&["./x/a", "./b"]),
+            ["tar", "czf", "out.tgz", "a", "b"]
+        );
+    }
+
+    #[test]
+    fn failing_commands_set_the_exit_code() {
+        let line = vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            "test {/} = ok || exit {/}".to_string(),
+        ];
+        let template = CommandTemplate::new(&line);
+
+        for threads in [None, Some(1)] {
+            assert!(exec_each(&template, &["./ok", "./ok"], threads).is_ok());
+            assert!(matches!(
+                exec_each(&template, &["./ok", "./3", "./4"], threads),
+                Err(CommandFailed(3))
+            ));
+        }
+
+        let line = vec!["sh".to_string(), "-c".to_string(), "exit 5".to_string()];
+        assert!(matches!(
+            exec_batch(&CommandTemplate::new(&line), &["./ok"]),
+            Err(CommandFailed(5))
+        ));
+    }
+}
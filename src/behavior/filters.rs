@@ -0,0 +1,211 @@
+use std::fs;
+
+use jiff::{
+    civil::{Date, DateTime, Time},
+    tz::TimeZone,
+    Timestamp,
+};
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::behavior::cache::FileMeta;
+use crate::cli::error::BFFError::{self, InvalidFilter};
+
+/// Metadata filters from `--newer`, `--older`, `--size` and `--executable`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Filters {
+    /// Files must be modified at or after this time (seconds since the epoch).
+    pub newer: Option<i64>,
+    /// Files must be modified before this time (seconds since the epoch).
+    pub older: Option<i64>,
+    pub size: Vec<SizeFilter>,
+    pub executable: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SizeFilter {
+    AtLeast(u64),
+    AtMost(u64),
+    Exactly(u64),
+}
+
+impl Filters {
+    pub fn new(
+        newer: Option<&str>,
+        older: Option<&str>,
+        size: &[String],
+        executable: bool,
+    ) -> Result<Self, BFFError> {
+        let now = Timestamp::now().as_second();
+
+        Ok(Filters {
+            newer: newer.map(|w| parse_time(w, now)).transpose()?,
+            older: older.map(|w| parse_time(w, now)).transpose()?,
+            size: size
+                .iter()
+                .map(|s| parse_size(s))
+                .collect::<Result<_, _>>()?,
+            executable,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.newer.is_none() && self.older.is_none() && self.size.is_empty() && !self.executable
+    }
+
+    /// Checks a file against every filter, using the cached metadata when there is some
+    /// and falling back to `stat` otherwise. Files that can't be read never pass.
+    pub fn matches(&self, path: &str, cached: Option<&FileMeta>) -> bool {
+        if self.is_empty() {
+            return true;
+        }
+
+        let meta = match cached {
+            Some(m) => *m,
+            None => {
+                info!("no cached metadata for {path}, reading it from disk");
+                match fs::metadata(path) {
+                    Ok(md) => FileMeta::from_metadata(&md),
+                    Err(_) => return false,
+                }
+            }
+        };
+
+        self.newer.is_none_or(|t| meta.mtime >= t)
+            && self.older.is_none_or(|t| meta.mtime < t)
+            && self.size.iter().all(|f| match *f {
+                SizeFilter::AtLeast(n) => meta.size >= n,
+                SizeFilter::AtMost(n) => meta.size <= n,
+                SizeFilter::Exactly(n) => meta.size == n,
+            })
+            && (!self.executable || meta.mode & 0o111 != 0)
+    }
+}
+
+/// Parses a point in time, either a duration before `now` (`30s`, `15m`, `12h`, `3d`, `2w`,
+/// `1y`) or a local date or date and time (`2024-01-01`, `2024-01-01T12:00`).
+fn parse_time(when: &str, now: i64) -> Result<i64, BFFError> {
+    let invalid = || InvalidFilter(format!("'{when}' is not a duration or a date"));
+
+    let split = when
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(when.len());
+    let (amount, unit) = when.split_at(split);
+
+    if let Ok(amount) = amount.parse::<i64>() {
+        let seconds = match unit {
+            "s" => 1,
+            "m" | "min" => 60,
+            "h" => 60 * 60,
+            "d" => 24 * 60 * 60,
+            "w" => 7 * 24 * 60 * 60,
+            "y" => 365 * 24 * 60 * 60,
+            _ => 0,
+        };
+
+        if seconds != 0 {
+            return amount
+                .checked_mul(seconds)
+                .and_then(|ago| now.checked_sub(ago))
+                .ok_or_else(|| InvalidFilter(format!("'{when}' is too long ago")));
+        }
+    }
+
+    let datetime = match when.parse::<DateTime>() {
+        Ok(dt) => dt,
+        Err(_) => when
+            .parse::<Date>()
+            .map_err(|_| invalid())?
+            .to_datetime(Time::midnight()),
+    };
+
+    datetime
+        .to_zoned(TimeZone::system())
+        .map(|z| z.timestamp().as_second())
+        .map_err(|_| invalid())
+}
+
+/// Parses a size like fd does: `+10M` is at least, `-1k` at most and `512` exactly that many
+/// bytes. `k`, `M`, `G` and `T` are powers of 1000, `ki`, `Mi`, `Gi` and `Ti` powers of 1024.
+fn parse_size(size: &str) -> Result<SizeFilter, BFFError> {
+    let invalid = || InvalidFilter(format!("'{size}' is not a size"));
+
+    let (kind, rest): (fn(u64) -> SizeFilter, &str) = match size.as_bytes().first() {
+        Some(b'+') => (SizeFilter::AtLeast, &size[1..]),
+        Some(b'-') => (SizeFilter::AtMost, &size[1..]),
+        _ => (SizeFilter::Exactly, size),
+    };
+
+    let split = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    let (amount, unit) = rest.split_at(split);
+    let amount: u64 = amount.parse().map_err(|_| invalid())?;
+
+    let unit = unit.to_lowercase();
+    let multiplier: u64 = match unit.trim_end_matches('b') {
+        "" => 1,
+        "k" => 1000,
+        "m" => 1000u64.pow(2),
+        "g" => 1000u64.pow(3),
+        "t" => 1000u64.pow(4),
+        "ki" => 1 << 10,
+        "mi" => 1 << 20,
+        "gi" => 1 << 30,
+        "ti" => 1 << 40,
+        _ => return Err(invalid()),
+    };
+
+    Ok(kind(amount.checked_mul(multiplier).ok_or_else(invalid)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes() {
+        assert_eq!(parse_size("+10M").unwrap(), SizeFilter::AtLeast(10_000_000));
+        assert_eq!(parse_size("-1k").unwrap(), SizeFilter::AtMost(1000));
+        assert_eq!(parse_size("2KiB").unwrap(), SizeFilter::Exactly(2048));
+        assert_eq!(parse_size("512").unwrap(), SizeFilter::Exactly(512));
+        assert!(parse_size("10X").is_err());
+        assert!(parse_size("+").is_err());
+    }
+
+    #[test]
+    fn times() {
+        assert_eq!(parse_time("3d", 1_000_000).unwrap(), 1_000_000 - 3 * 86400);
+        assert_eq!(parse_time("90s", 1_000).unwrap(), 910);
+        assert!(parse_time("2024-01-01", 0).is_ok());
+        assert!(parse_time("2024-01-01T12:30", 0).is_ok());
+        assert!(parse_time("soon", 0).is_err());
+        assert!(parse_time("999999999999999d", 0).is_err());
+    }
+
+    #[test]
+    fn filters_use_cached_metadata() {
+        let filters = Filters {
+            newer: Some(100),
+            size: vec![SizeFilter::AtLeast(10)],
+            executable: true,
+            ..Default::default()
+        };
+        let meta = FileMeta {
+            mtime: 150,
+            size: 20,
+            mode: 0o755,
+            archive: None,
+        };
+
+        assert!(filters.matches("/does/not/exist", Some(&meta)));
+        assert!(!filters.matches(
+            "/does/not/exist",
+            Some(&FileMeta {
+                mode: 0o644,
+                ..meta
+            })
+        ));
+        assert!(!filters.matches("/does/not/exist", None));
+    }
+}
@@ -0,0 +1,148 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{self, Path, PathBuf},
+};
+
+use jiff::Timestamp;
+use log::{info, warn};
+use rmp_serde::{decode, encode};
+use serde::{Deserialize, Serialize};
+
+use crate::cli::error::BFFError;
+
+pub const FRECENCY_FILE: &str = "frecency.bff";
+
+/// Entries beyond this are dropped, least frecent first.
+const MAX_ENTRIES: usize = 1000;
+
+/// Remembers which files were picked or opened through bff, and when.
+#[derive(Default, Serialize, Deserialize, Debug)]
+pub(crate) struct Frecency {
+    visits: HashMap<PathBuf, Visits>,
+}
+
+#[derive(Default, Serialize, Deserialize, Debug, Clone, Copy)]
+struct Visits {
+    count: u32,
+    /// Last visit, in seconds since the epoch.
+    last: i64,
+}
+
+impl Visits {
+    /// Visit count weighted by how recent the last visit is.
+    fn score(&self, now: i64) -> u64 {
+        let weight = match now - self.last {
+            age if age < 60 * 60 => 16,
+            age if age < 24 * 60 * 60 => 8,
+            age if age < 7 * 24 * 60 * 60 => 2,
+            _ => 1,
+        };
+        u64::from(self.count) * weight
+    }
+}
+
+/// The store lives in the XDG data directory, `~/.local/share/bff` by default.
+fn store_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|d| d.join(env!("CARGO_PKG_NAME")).join(FRECENCY_FILE))
+}
+
+impl Frecency {
+    /// Reads the store, a corrupt one is started over rather than failing searches.
+    pub fn load() -> Result<Self, BFFError> {
+        match store_path().filter(|p| p.exists()) {
+            Some(path) => Frecency::read(&path),
+            None => Ok(Frecency::default()),
+        }
+    }
+
+    fn read(path: &Path) -> Result<Self, BFFError> {
+        info!("reading frecency store from {}", path.display());
+        match decode::from_slice(&fs::read(path)?) {
+            Ok(store) => Ok(store),
+            Err(e) => {
+                warn!("ignoring corrupt frecency store {}: {e}", path.display());
+                Ok(Frecency::default())
+            }
+        }
+    }
+
+    fn save(&self) -> Result<(), BFFError> {
+        let Some(path) = store_path() else {
+            return Ok(());
+        };
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        info!("writing frecency store to {}", path.display());
+        fs::write(path, encode::to_vec(self)?)?;
+        Ok(())
+    }
+
+    /// Frecency of a path relative to the working directory, 0 if it was never visited.
+    pub fn score(&self, path: &str) -> u64 {
+        let now = Timestamp::now().as_second();
+        path::absolute(path)
+            .ok()
+            .and_then(|p| self.visits.get(&p))
+            .map_or(0, |v| v.score(now))
+    }
+
+    /// Records a visit to every path in `paths`, relative to the working directory.
+    pub fn record<S: AsRef<str>>(paths: &[S]) -> Result<(), BFFError> {
+        let mut store = Frecency::load()?;
+        let now = Timestamp::now().as_second();
+
+        for p in paths {
+            let abs = path::absolute(p.as_ref())?;
+            info!("recording visit to {}", abs.display());
+            let visits = store.visits.entry(abs).or_default();
+            visits.count += 1;
+            visits.last = now;
+        }
+
+        if store.visits.len() > MAX_ENTRIES {
+            let mut scores: Vec<(PathBuf, u64)> = store
+                .visits
+                .iter()
+                .map(|(p, v)| (p.clone(), v.score(now)))
+                .collect();
+            scores.sort_by_key(|s| s.1);
+
+            for (p, _) in scores.into_iter().take(store.visits.len() - MAX_ENTRIES) {
+                store.visits.remove(&p);
+            }
+        }
+
+        store.save()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recent_visits_weigh_more() {
+        let now = 10_000_000;
+        let recent = Visits {
+            count: 1,
+            last: now - 10,
+        };
+        let old = Visits {
+            count: 3,
+            last: now - 30 * 24 * 60 * 60,
+        };
+
+        assert!(recent.score(now) > old.score(now));
+    }
+
+    #[test]
+    fn corrupt_stores_start_over() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        fs::write(file.path(), b"\xc1 not msgpack").unwrap();
+        assert!(Frecency::read(file.path()).unwrap().visits.is_empty());
+    }
+}
@@ -0,0 +1,128 @@
+use std::{
+    env,
+    fmt::Write,
+    io::{self, IsTerminal},
+    path::{Path, PathBuf},
+};
+
+use crate::cli::error::BFFError;
+use crate::options::When;
+
+/// Whether result paths are printed as links. `auto` only does so in terminals known to
+/// support OSC 8, others may print the escape sequences as garbage.
+pub fn use_hyperlinks(choice: When) -> bool {
+    match choice {
+        When::Always => true,
+        When::Never => false,
+        When::Auto => io::stdout().is_terminal() && supports_hyperlinks(),
+    }
+}
+
+fn supports_hyperlinks() -> bool {
+    let var = |name| env::var(name).unwrap_or_default();
+
+    let term = var("TERM");
+    if term == "dumb" {
+        return false;
+    }
+
+    [
+        "WT_SESSION",
+        "KITTY_WINDOW_ID",
+        "KONSOLE_VERSION",
+        "WEZTERM_EXECUTABLE",
+        "GHOSTTY_RESOURCES_DIR",
+    ]
+    .iter()
+    .any(|name| env::var_os(name).is_some())
+        || matches!(
+            var("TERM_PROGRAM").as_str(),
+            "iTerm.app" | "WezTerm" | "vscode" | "ghostty" | "Hyper"
+        )
+        || var("VTE_VERSION").parse::<u32>().is_ok_and(|v| v >= 5000)
+        || ["xterm-kitty", "foot", "alacritty"]
+            .iter()
+            .any(|t| term.starts_with(t))
+}
+
+/// Wraps result paths in OSC 8 escape sequences linking to the files.
+#[derive(Debug)]
+pub struct Hyperlinks {
+    root: PathBuf,
+}
+
+impl Hyperlinks {
+    /// Links paths relative to the working directory.
+    pub fn new() -> Result<Self, BFFError> {
+        Ok(Hyperlinks {
+            root: env::current_dir()?,
+        })
+    }
+
+    /// The `file://` URL of `path`.
+    fn url(&self, path: &str) -> String {
+        let abs = self
+            .root
+            .join(Path::new(path).strip_prefix(".").unwrap_or(path.as_ref()));
+
+        let mut url = String::from("file://");
+        for &b in abs.as_os_str().as_encoded_bytes() {
+            match b {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                    url.push(b as char)
+                }
+                _ => {
+                    let _ = write!(url, "%{b:02X}");
+                }
+            }
+        }
+        url
+    }
+
+    /// Makes `text` a link to `path`.
+    pub fn link(&self, path: &str, text: &str) -> String {
+        format!("\x1b]8;;{}\x1b\\{text}\x1b]8;;\x1b\\", self.url(path))
+    }
+
+    /// Makes the leaf, on the last line of a tree drawn by `path_to_tree`, a link to `path`.
+    pub fn link_tree(&self, path: &str, tree: &str) -> String {
+        let body = tree.trim_end_matches('\n');
+        let (lines, leaf) = body.rsplit_once('\n').map_or(("", body), |(l, f)| (l, f));
+        let (branch, name) = leaf.split_at(leaf.find("─ ").map_or(0, |i| i + "─ ".len()));
+
+        let mut linked = String::new();
+        if !lines.is_empty() {
+            linked.push_str(lines);
+            linked.push('\n');
+        }
+        linked.push_str(branch);
+        linked.push_str(&self.link(path, name));
+        linked.push('\n');
+        linked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn links_point_at_absolute_files() {
+        let links = Hyperlinks {
+            root: PathBuf::from("/home/me"),
+        };
+
+        assert_eq!(
+            links.url("./docs/100% done.txt"),
+            "file:///home/me/docs/100%25%20done.txt"
+        );
+        assert_eq!(
+            links.url("./backups/a!/notes.md"),
+            "file:///home/me/backups/a%21/notes.md"
+        );
+        assert_eq!(
+            links.link_tree("./docs/a.md", "└─ docs\n   └─ a.md\n"),
+            "└─ docs\n   └─ \x1b]8;;file:///home/me/docs/a.md\x1b\\a.md\x1b]8;;\x1b\\\n"
+        );
+    }
+}
@@ -0,0 +1,274 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{BufRead, BufReader, Read, Write},
+};
+
+use log::info;
+use rmp_serde::{decode, encode};
+use serde::{Deserialize, Serialize};
+
+use crate::behavior::cache::{load_file_tree, FileTree, TreeOptions, CACHE_FILE};
+use crate::behavior::checksum;
+use crate::cli::arg_parser::IndexArgs;
+use crate::cli::error;
+use crate::options::MatchOptions;
+use crate::parser::normalize::normalize;
+use crate::parser::term::Term;
+
+pub const INDEX_FILE: &str = ".index.bff";
+
+/// Maps every trigram to the files containing it, so a query only has to look at files
+/// containing all the trigrams of one of its terms.
+///
+/// Paths are indexed without diacritics and lowercased, so the index works for any
+/// combination of `--case` and `--fold`.
+#[derive(Default, Serialize, Deserialize, Debug)]
+pub(crate) struct TrigramIndex {
+    /// Ascending indices into `FileTree.files`, by trigram.
+    postings: HashMap<u32, Vec<u32>>,
+}
+
+fn key_options() -> MatchOptions {
+    MatchOptions {
+        ignore_case: true,
+        fold: true,
+        ..Default::default()
+    }
+}
+
+fn trigrams(s: &str) -> impl Iterator<Item = u32> + '_ {
+    s.as_bytes()
+        .windows(3)
+        .map(|w| u32::from_be_bytes([0, w[0], w[1], w[2]]))
+}
+
+/// Sorted intersection of two ascending lists.
+fn intersect(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let (mut i, mut j) = (0, 0);
+    let mut out = vec![];
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                out.push(a[i]);
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    out
+}
+
+impl TrigramIndex {
+    pub fn build(tree: &FileTree) -> Self {
+        info!("building trigram index");
+
+        let opts = key_options();
+        let mut postings: HashMap<u32, Vec<u32>> = HashMap::new();
+
+        for (i, path) in tree.files.iter().enumerate() {
+            let i = i as u32;
+            for t in trigrams(&normalize(path, &opts)) {
+                let list = postings.entry(t).or_default();
+                if list.last() != Some(&i) {
+                    list.push(i);
+                }
+            }
+        }
+
+        TrigramIndex { postings }
+    }
+
+    /// Files containing every trigram of `s`, `None` when `s` is too short to have any.
+    fn lookup(&self, s: &str) -> Option<Vec<u32>> {
+        let key = normalize(s, &key_options());
+        let mut grams: Vec<u32> = trigrams(&key).collect();
+        if grams.is_empty() {
+            return None;
+        }
+
+        grams.sort_unstable();
+        grams.dedup();
+
+        let mut lists: Vec<&[u32]> = grams
+            .iter()
+            .map(|t| self.postings.get(t).map_or(&[][..], Vec::as_slice))
+            .collect();
+        // starting with the rarest trigram keeps the intermediate lists short
+        lists.sort_by_key(|l| l.len());
+
+        let mut found = lists[0].to_vec();
+        for list in &lists[1..] {
+            if found.is_empty() {
+                break;
+            }
+            found = intersect(&found, list);
+        }
+
+        Some(found)
+    }
+
+    /// Files that can match a single term, `None` when the index can't narrow it down.
+    fn term_candidates(&self, term: &Term) -> Option<Vec<u32>> {
+        term.literals()?
+            .into_iter()
+            .filter_map(|l| self.lookup(l))
+            .reduce(|a, b| intersect(&a, &b))
+    }
+
+    /// Files that can match the query, in tree order: the ones matching every term for a
+    /// strict search and any term for a soft one. `None` means every file has to be checked.
+    pub fn candidates(&self, terms: &[Term], strict: bool) -> Option<Vec<u32>> {
+        if terms.is_empty() {
+            return None;
+        }
+
+        let sets = terms.iter().map(|t| self.term_candidates(t));
+
+        if strict {
+            sets.flatten().reduce(|a, b| intersect(&a, &b))
+        } else {
+            let mut all = vec![];
+            for set in sets {
+                all.extend(set?);
+            }
+            all.sort_unstable();
+            all.dedup();
+            Some(all)
+        }
+    }
+}
+
+pub fn index_exists() -> Result<bool, error::BFFError> {
+    Ok(fs::exists(INDEX_FILE)?)
+}
+
+/// Writes the index with the same header as the cache file, so it is only used with the
+/// file tree it was built from.
+pub fn write_index_file(
+    checksum: &str,
+    index: &TrigramIndex,
+    tree_opts: &TreeOptions,
+) -> Result<(), error::BFFError> {
+    info!("writing to index file");
+
+    let mut file = File::create(INDEX_FILE)?;
+    writeln!(file, "{checksum}\n{}", tree_opts.flags())?;
+
+    let buf = encode::to_vec(index)?;
+    file.write_all(&buf)?;
+
+    Ok(())
+}
+
+/// Reads the index if there is one built for this checksum and these options.
+pub fn read_index_file(
+    checksum: &str,
+    tree_opts: &TreeOptions,
+) -> Result<Option<TrigramIndex>, error::BFFError> {
+    if !index_exists()? {
+        return Ok(None);
+    }
+
+    info!("reading from index file");
+
+    let mut reader = BufReader::new(File::open(INDEX_FILE)?);
+    let mut sum = String::new();
+    let mut flags = String::new();
+    reader.read_line(&mut sum)?;
+    reader.read_line(&mut flags)?;
+
+    if sum.trim_end() != checksum || flags.trim_end() != tree_opts.flags() {
+        info!("index file is out of date, ignoring it");
+        return Ok(None);
+    }
+
+    let mut buf = Vec::new();
+    reader.read_to_end(&mut buf)?;
+
+    Ok(Some(decode::from_slice(&buf)?))
+}
+
+/// The index for `tree`, if the user built one with `bff index`. When the tree changed,
+/// the index file lists other files: it is rebuilt along with the cache file, and left
+/// unused when the cache file isn't written.
+pub fn load_index(
+    checksum: &str,
+    tree: &FileTree,
+    changed: bool,
+    skip_cache: bool,
+    tree_opts: &TreeOptions,
+) -> Result<Option<TrigramIndex>, error::BFFError> {
+    if !changed {
+        return read_index_file(checksum, tree_opts);
+    }
+
+    if skip_cache || !index_exists()? {
+        return Ok(None);
+    }
+
+    let index = TrigramIndex::build(tree);
+    write_index_file(checksum, &index, tree_opts)?;
+    Ok(Some(index))
+}
+
+/// Builds or refreshes the file tree and its index.
+pub fn run_index(args: IndexArgs) -> Result<(), error::BFFError> {
+    let tree_opts = TreeOptions {
+        show_hidden: args.show_hidden,
+        archives: args.archives,
+    };
+
+    // creating a file changes the mtime of the directory, which the checksum is made of,
+    // so both files have to exist before it is computed
+    for file in [CACHE_FILE, INDEX_FILE] {
+        if !fs::exists(file)? {
+            File::create(file)?;
+        }
+    }
+
+    let sum = checksum::gen_checksum()?;
+    let (tree, _) = load_file_tree(&sum, false, &tree_opts)?;
+    write_index_file(&sum, &TrigramIndex::build(&tree), &tree_opts)?;
+
+    println!("indexed {} files", tree.files.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index() -> TrigramIndex {
+        TrigramIndex::build(&FileTree {
+            files: Box::new([
+                "./docs/Résumé.pdf".to_string(),
+                "./docs/report.docx".to_string(),
+                "./src/main.rs".to_string(),
+            ]),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn soft_queries_union_terms() {
+        let index = index();
+        let terms = [Term::fuzzy("resume"), Term::fuzzy("main")];
+        assert_eq!(index.candidates(&terms, false), Some(vec![0, 2]));
+        assert_eq!(index.candidates(&terms, true), Some(vec![]));
+
+        // a short term could be anywhere
+        let terms = [Term::fuzzy("re"), Term::fuzzy("main")];
+        assert_eq!(index.candidates(&terms, false), None);
+        assert_eq!(index.candidates(&terms, true), Some(vec![2]));
+    }
+
+    #[test]
+    fn path_terms_need_every_part() {
+        let index = index();
+        let term = Term::parse("docs/rep").unwrap();
+        assert_eq!(index.candidates(&[term], false), Some(vec![1]));
+    }
+}
@@ -0,0 +1,17 @@
+use crate::cli::arg_parser::{InitArgs, Shell};
+
+const BASH: &str = include_str!("init/init.bash");
+const ZSH: &str = include_str!("init/init.zsh");
+const FISH: &str = include_str!("init/init.fish");
+
+/// Prints the script defining `bcd` and the Ctrl-T binding for the shell.
+pub fn run_init(args: InitArgs) {
+    print!(
+        "{}",
+        match args.shell {
+            Shell::Bash => BASH,
+            Shell::Zsh => ZSH,
+            Shell::Fish => FISH,
+        }
+    );
+}
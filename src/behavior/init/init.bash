@@ -0,0 +1,21 @@
+# bff shell integration for bash, load it with:
+#   eval "$(bff init bash)"
+
+# bcd TERMS...: cd to the directory of the best match
+bcd() {
+    local dir
+    dir="$(command bff search --dir-of -c 1 --no-tree --format plain --color never --hyperlink never "$@")" && [ -n "$dir" ] && builtin cd -- "$dir"
+}
+
+# Ctrl-T: pick files and insert them at the cursor
+__bff_insert() {
+    local picked path
+    picked="$(command bff pick | while IFS= read -r path; do printf '%q ' "$path"; done)"
+    READLINE_LINE="${READLINE_LINE:0:$READLINE_POINT}$picked${READLINE_LINE:$READLINE_POINT}"
+    READLINE_POINT=$((READLINE_POINT + ${#picked}))
+}
+
+if [[ $- == *i* ]] && ((BASH_VERSINFO[0] >= 4)); then
+    bind -m emacs-standard -x '"\C-t": __bff_insert'
+    bind -m vi-insert -x '"\C-t": __bff_insert'
+fi
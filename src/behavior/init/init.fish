@@ -0,0 +1,21 @@
+# bff shell integration for fish, load it with:
+#   bff init fish | source
+
+# bcd TERMS...: cd to the directory of the best match
+function bcd
+    set -l dir (command bff search --dir-of -c 1 --no-tree --format plain --color never --hyperlink never $argv)
+    and test -n "$dir"
+    and builtin cd -- $dir
+end
+
+# Ctrl-T: pick files and insert them at the cursor
+function __bff_insert
+    set -l picked (command bff pick)
+    and commandline -i -- (string escape -- $picked | string join ' ')' '
+    commandline -f repaint
+end
+
+if status is-interactive
+    bind \ct __bff_insert
+    bind -M insert \ct __bff_insert
+end
@@ -1,5 +1,23 @@
+pub mod archives;
 pub mod cache;
 pub mod checksum;
+pub mod completions;
+pub mod config;
+pub mod content;
+#[cfg(unix)]
+pub mod daemon;
+pub mod exec;
+pub mod filters;
+pub mod frecency;
+pub mod hyperlink;
+pub mod index;
+pub mod init;
+pub mod open;
+pub mod output;
+pub mod pick;
+pub mod score;
 pub mod search;
 pub mod strings;
+pub mod theme;
 pub mod tree;
+pub mod watch;
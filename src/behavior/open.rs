@@ -0,0 +1,246 @@
+use std::{
+    collections::HashMap,
+    env,
+    fs::File,
+    io::{self, BufRead, IsTerminal, Read, Write},
+    path::Path,
+    str,
+};
+
+use log::{info, warn};
+
+use crate::behavior::exec::{self, CommandTemplate};
+use crate::behavior::filters::Filters;
+use crate::behavior::frecency::Frecency;
+use crate::behavior::pick::pick;
+use crate::behavior::search::{find, match_options, Hit};
+use crate::cli::arg_parser::SearchArgs;
+use crate::cli::error::BFFError::{self, Aborted};
+use crate::config::schema::TreeConfig;
+use crate::parser::alias_expansion::ExpandAlias;
+use crate::parser::term::{parse_terms, Query};
+
+/// Only this many bytes are read when deciding if a file is text.
+const TEXT_PROBE: u64 = 8192;
+
+/// At most this many results are offered when the best match is ambiguous.
+const MAX_CHOICES: usize = 9;
+
+/// Opens files that aren't text, or when no editor is set.
+#[cfg(target_os = "macos")]
+const SYSTEM_OPENER: &[&str] = &["open"];
+#[cfg(windows)]
+const SYSTEM_OPENER: &[&str] = &["cmd", "/c", "start", ""];
+#[cfg(not(any(target_os = "macos", windows)))]
+const SYSTEM_OPENER: &[&str] = &["xdg-open"];
+
+/// Whether the start of the file is UTF-8 without NUL bytes.
+fn is_text(path: &str) -> bool {
+    let mut head = vec![];
+    let read = File::open(path).and_then(|f| f.take(TEXT_PROBE).read_to_end(&mut head));
+    if read.is_err() || head.contains(&0) {
+        return false;
+    }
+
+    match str::from_utf8(&head) {
+        Ok(_) => true,
+        // the probe may end in the middle of a character
+        Err(e) => e.error_len().is_none(),
+    }
+}
+
+/// The command line opening `path`: the `[open]` command for its extension unless it is
+/// blank, `$VISUAL` or `$EDITOR` for text files, and the system opener otherwise.
+fn opener(
+    path: &str,
+    open: &HashMap<String, String>,
+    var: impl Fn(&str) -> Option<String>,
+) -> Vec<String> {
+    let ext = Path::new(path).extension().and_then(|e| e.to_str());
+    let configured = ext.and_then(|ext| {
+        open.iter()
+            .find(|(k, _)| k.trim_start_matches('.').eq_ignore_ascii_case(ext))
+            .map(|(_, cmd)| cmd.clone())
+            .filter(|cmd| !cmd.trim().is_empty())
+    });
+    let editor = || {
+        var("VISUAL")
+            .or_else(|| var("EDITOR"))
+            .filter(|e| !e.trim().is_empty())
+    };
+
+    let line: Vec<String> = match configured {
+        Some(cmd) => cmd.split_whitespace().map(str::to_string).collect(),
+        None => match editor().filter(|_| is_text(path)) {
+            Some(cmd) => cmd.split_whitespace().map(str::to_string).collect(),
+            None => SYSTEM_OPENER.iter().map(|s| s.to_string()).collect(),
+        },
+    };
+
+    CommandTemplate::new(&line).expand(path)
+}
+
+/// The results scoring as high as the best one.
+fn ties(hits: &[Hit]) -> &[Hit] {
+    let n = hits.iter().take_while(|h| h.score == hits[0].score).count();
+    &hits[..n.min(MAX_CHOICES)]
+}
+
+/// Lists the results on `out` and reads the number of the one to open from `input`, an
+/// empty line picks the first one.
+fn choose(ties: &[Hit], mut input: impl BufRead, mut out: impl Write) -> Result<&Hit, BFFError> {
+    for (i, hit) in ties.iter().enumerate() {
+        writeln!(out, "{}) {}", i + 1, hit.path)?;
+    }
+    write!(out, "open which? [1-{}] ", ties.len())?;
+    out.flush()?;
+
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        return Err(Aborted);
+    }
+
+    match line.trim() {
+        "" => Ok(&ties[0]),
+        n => n
+            .parse::<usize>()
+            .ok()
+            .and_then(|n| n.checked_sub(1))
+            .and_then(|i| ties.get(i))
+            .ok_or(Aborted),
+    }
+}
+
+/// Searches like `bff search` and returns the best match. When several files match as
+/// well, the picker is opened in a terminal and a numbered list is offered otherwise.
+fn best_match(obj: &SearchArgs, conf: &TreeConfig) -> Result<Hit, BFFError> {
+    let expd = obj.terms.expand(conf);
+    info!("after alias expansion: {expd:?}");
+
+    let opts = match_options(obj, &expd);
+    let query = Query {
+        terms: parse_terms(&expd, &obj.regex, &obj.glob, &opts)?,
+        content: parse_terms(&obj.content, &[], &[], &opts)?,
+    };
+    let filters = Filters::new(
+        obj.newer.as_deref(),
+        obj.older.as_deref(),
+        &obj.size,
+        obj.executable,
+    )?;
+
+    let hits = find(obj, expd, &query, &opts, &filters, MAX_CHOICES as u32)?;
+    let ties = ties(&hits);
+    if ties.len() == 1 {
+        return Ok(ties[0].clone());
+    }
+
+    info!("{} results tie for the best match", ties.len());
+    if io::stdin().is_terminal() && io::stderr().is_terminal() {
+        pick(obj, conf)?.into_iter().next().ok_or(Aborted)
+    } else {
+        Ok(choose(ties, io::stdin().lock(), io::stderr())?.clone())
+    }
+}
+
+/// Opens the best match for the search, `--interactive` always lets you pick it.
+pub fn run_open(obj: SearchArgs, conf: TreeConfig) -> Result<(), BFFError> {
+    let hit = if obj.interactive {
+        pick(&obj, &conf)?.into_iter().next().ok_or(Aborted)?
+    } else {
+        best_match(&obj, &conf)?
+    };
+
+    if let Err(e) = Frecency::record(&[&hit.path]) {
+        warn!("unable to record visit: {e}");
+    }
+
+    // members of an archive open the archive
+    let file = hit.file();
+    let line = opener(file, &conf.open, |name| env::var(name).ok());
+    info!("opening {file} with {line:?}");
+
+    exec::run(&line)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io::Cursor};
+
+    use super::*;
+    use crate::behavior::score::Score;
+
+    fn hit(path: &str, matched: usize) -> Hit {
+        Hit {
+            path: path.to_string(),
+            score: Score { matched, bonus: 0 },
+            meta: None,
+            ranges: vec![],
+            content: None,
+        }
+    }
+
+    #[test]
+    fn ambiguous_results_are_numbered() {
+        let hits = [hit("./a.md", 2), hit("./b.md", 2), hit("./c.md", 1)];
+        let ties = ties(&hits);
+        assert_eq!(ties.len(), 2);
+
+        let mut out = vec![];
+        let chosen = choose(ties, Cursor::new("2\n"), &mut out).unwrap();
+        assert_eq!(chosen.path, "./b.md");
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "1) ./a.md\n2) ./b.md\nopen which? [1-2] "
+        );
+
+        assert!(choose(ties, Cursor::new("3\n"), io::sink()).is_err());
+        assert_eq!(
+            choose(ties, Cursor::new("\n"), io::sink()).unwrap().path,
+            "./a.md"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn opens_with_a_stub_command() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let stub = dir.join("stub.sh");
+        let log = dir.join("log");
+        fs::write(
+            &stub,
+            format!("#!/bin/sh\necho \"$@\" > {}\n", log.display()),
+        )
+        .unwrap();
+        fs::set_permissions(&stub, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let text = dir.join("notes.txt");
+        fs::write(&text, "hello").unwrap();
+        let stub = stub.display().to_string();
+        let open = HashMap::from([
+            (".pdf".to_string(), format!("{stub} --page 1 {{}}")),
+            ("txt".to_string(), " ".to_string()),
+            ("bin".to_string(), String::new()),
+        ]);
+        let editor = |name: &str| (name == "EDITOR").then(|| format!("{stub} -w"));
+
+        let pdf = dir.join("paper.PDF").display().to_string();
+        exec::run(&opener(&pdf, &open, editor)).unwrap();
+        assert_eq!(
+            fs::read_to_string(&log).unwrap(),
+            format!("--page 1 {pdf}\n")
+        );
+
+        let text = text.display().to_string();
+        exec::run(&opener(&text, &open, editor)).unwrap();
+        assert_eq!(fs::read_to_string(&log).unwrap(), format!("-w {text}\n"));
+
+        assert_eq!(
+            opener(&dir.join("none.bin").display().to_string(), &open, editor)[0],
+            SYSTEM_OPENER[0]
+        );
+    }
+}
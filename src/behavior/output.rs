@@ -0,0 +1,203 @@
+use std::{
+    env,
+    io::{self, IsTerminal, Write},
+};
+
+use serde::Serialize;
+
+use crate::behavior::hyperlink::Hyperlinks;
+use crate::behavior::score::Score;
+use crate::behavior::search::Hit;
+use crate::behavior::strings::{find_term_ranges, TermRange};
+use crate::behavior::theme::Palette;
+use crate::behavior::tree::path_to_tree;
+use crate::cli::error::BFFError;
+use crate::options::{MatchOptions, OutputFormat, When};
+use crate::parser::normalize::Normalized;
+use crate::parser::term::Query;
+
+/// A result as printed by the structured formats.
+#[derive(Debug, Serialize)]
+struct Record<'a> {
+    path: &'a str,
+    score: Score,
+    /// The query terms found in the path, as they were typed.
+    terms: Vec<String>,
+    /// Byte ranges of `path` matched by the terms, with the index of the term.
+    ranges: &'a [TermRange],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content: Option<ContentRecord<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+struct ContentRecord<'a> {
+    line: usize,
+    snippet: &'a str,
+    /// Byte ranges of `snippet` matched by the content terms, with the index of the term.
+    ranges: &'a [TermRange],
+}
+
+#[derive(Debug, Serialize)]
+struct Removed<'a> {
+    path: &'a str,
+    removed: bool,
+}
+
+/// Whether matches are highlighted. `auto` follows https://no-color.org and only
+/// highlights when stdout is a terminal.
+pub fn use_color(choice: When) -> bool {
+    match choice {
+        When::Always => true,
+        When::Never => false,
+        When::Auto => {
+            env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()) && io::stdout().is_terminal()
+        }
+    }
+}
+
+/// Prints results in the format picked with `--format`.
+pub struct Printer<'a> {
+    pub format: OutputFormat,
+    pub query: &'a Query,
+    pub opts: &'a MatchOptions,
+    /// Draw the tree of every path, only used by the plain format.
+    pub tree: bool,
+    pub palette: &'a Palette,
+    /// Link paths to the files, only used by the plain format.
+    pub links: Option<Hyperlinks>,
+}
+
+impl Printer<'_> {
+    fn record<'h>(&self, hit: &'h Hit) -> Record<'h> {
+        let text = Normalized::new(&hit.path, self.opts);
+        let mtime = hit.meta.filter(|_| self.opts.mtime).map(|m| m.mtime);
+
+        Record {
+            path: &hit.path,
+            score: hit.score,
+            terms: self
+                .query
+                .terms
+                .iter()
+                .filter(|t| t.is_match(&text) || mtime.is_some_and(|m| t.matches_mtime(m)))
+                .map(ToString::to_string)
+                .collect(),
+            ranges: &hit.ranges,
+            content: hit.content.as_ref().map(|c| ContentRecord {
+                line: c.line,
+                snippet: &c.snippet,
+                ranges: &c.ranges,
+            }),
+        }
+    }
+
+    /// Prints every result of a search.
+    pub fn print_all(&self, hits: &[Hit]) -> Result<(), BFFError> {
+        if self.format != OutputFormat::Json {
+            return hits.iter().try_for_each(|hit| self.print(hit));
+        }
+
+        let records: Vec<Record> = hits.iter().map(|h| self.record(h)).collect();
+        println!("{}", serde_json::to_string(&records)?);
+        Ok(())
+    }
+
+    /// Prints a single result. JSON can't be streamed, so it is printed as a JSON line.
+    pub fn print(&self, hit: &Hit) -> Result<(), BFFError> {
+        match self.format {
+            OutputFormat::Plain => {
+                let mut path = self.palette.paint_path(&hit.path, &hit.ranges);
+                // members of an archive link to the archive
+                if let Some(links) = &self.links {
+                    path = links.link(hit.file(), &path);
+                }
+                match &hit.content {
+                    Some(c) => println!(
+                        "{path}:{}: {}",
+                        c.line,
+                        self.palette.paint(&c.snippet, &c.ranges)
+                    ),
+                    None => println!("{path}"),
+                }
+                if self.tree {
+                    let mut tree = path_to_tree(&hit.path, hit.archive())?;
+                    if let Some(links) = &self.links {
+                        tree = links.link_tree(hit.file(), &tree);
+                    }
+                    println!("{}", self.palette.paint_tree(&tree));
+                }
+            }
+            OutputFormat::Json | OutputFormat::Jsonl => {
+                println!("{}", serde_json::to_string(&self.record(hit))?)
+            }
+            OutputFormat::Null => {
+                let mut out = io::stdout().lock();
+                out.write_all(hit.path.as_bytes())?;
+                out.write_all(b"\0")?;
+                out.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Prints a result that went away under `--watch --removed`.
+    pub fn print_removed(&self, path: &str) -> Result<(), BFFError> {
+        if let Some(line) = self.removed_line(path)? {
+            println!("{line}");
+        }
+        Ok(())
+    }
+
+    /// The line printed for a result that went away. There is no way to tell removals
+    /// apart in NUL separated output, so they are left out of it.
+    pub fn removed_line(&self, path: &str) -> Result<Option<String>, BFFError> {
+        Ok(match self.format {
+            OutputFormat::Plain => Some(format!(
+                "- {}",
+                self.palette
+                    .paint_path(path, &find_term_ranges(path, &self.query.terms, self.opts))
+            )),
+            OutputFormat::Json | OutputFormat::Jsonl => Some(serde_json::to_string(&Removed {
+                path,
+                removed: true,
+            })?),
+            OutputFormat::Null => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::schema::Theme;
+    use crate::parser::term::Term;
+
+    #[test]
+    fn records_list_matched_terms() {
+        let query: Query = vec![Term::fuzzy("docs"), Term::fuzzy("zzz")].into();
+        let opts = MatchOptions::default();
+        let printer = Printer {
+            format: OutputFormat::Jsonl,
+            query: &query,
+            opts: &opts,
+            tree: false,
+            palette: &Palette::new(&Theme::default()).unwrap(),
+            links: None,
+        };
+        let hit = Hit {
+            path: "./docs/docs.md".to_string(),
+            score: Score {
+                matched: 1,
+                bonus: 0,
+            },
+            meta: None,
+            ranges: vec![(2, 6, 0), (7, 11, 0)],
+            content: None,
+        };
+
+        assert_eq!(
+            serde_json::to_string(&printer.record(&hit)).unwrap(),
+            r#"{"path":"./docs/docs.md","score":{"matched":1,"bonus":0},"terms":["docs"],"ranges":[[2,6,0],[7,11,0]]}"#
+        );
+    }
+}
@@ -0,0 +1,413 @@
+use std::io::{self, Stderr};
+
+use log::{info, warn};
+use ratatui::{
+    backend::CrosstermBackend,
+    crossterm::{
+        event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+        execute,
+        terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    },
+    layout::{Constraint, Layout, Position},
+    style::{Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, List, ListItem, ListState, Paragraph},
+    Frame, Terminal,
+};
+
+use crate::behavior::cache::{self, FileTree, TreeOptions};
+use crate::behavior::checksum;
+use crate::behavior::filters::Filters;
+use crate::behavior::frecency::Frecency;
+use crate::behavior::score::Score;
+use crate::behavior::search::{match_options, rank_in_tree, Hit};
+use crate::behavior::strings::find_term_ranges;
+use crate::behavior::theme::Palette;
+use crate::behavior::tree::path_to_tree;
+use crate::cli::arg_parser::SearchArgs;
+use crate::cli::error::BFFError::{self, Aborted, NoResult};
+use crate::config::schema::TreeConfig;
+use crate::options::MatchOptions;
+use crate::parser::alias_expansion::ExpandAlias;
+use crate::parser::term::{parse_terms, Query, Term};
+
+/// Puts the terminal back the way it was, even when the picker panics.
+///
+/// The picker draws on stderr so the chosen paths on stdout can be piped or captured,
+/// as in `vim $(bff pick)`.
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn new() -> Result<(Self, Terminal<CrosstermBackend<Stderr>>), BFFError> {
+        enable_raw_mode()?;
+        execute!(io::stderr(), EnterAlternateScreen)?;
+        Ok((
+            TerminalGuard,
+            Terminal::new(CrosstermBackend::new(io::stderr()))?,
+        ))
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = disable_raw_mode();
+        let _ = execute!(io::stderr(), LeaveAlternateScreen);
+    }
+}
+
+struct Picker<'a> {
+    obj: &'a SearchArgs,
+    conf: &'a TreeConfig,
+    tree: &'a FileTree,
+    filters: Filters,
+    palette: Palette,
+    input: String,
+    /// Terms and options of the last query that parsed, used for highlighting.
+    terms: Vec<Term>,
+    opts: MatchOptions,
+    results: Vec<Hit>,
+    /// Index of the highlighted result, and of the first one on screen.
+    selected: usize,
+    offset: usize,
+    chosen: Vec<Hit>,
+    error: Option<String>,
+}
+
+impl Picker<'_> {
+    /// Re-runs the search for the current input.
+    fn update(&mut self) {
+        let words: Vec<String> = self.input.split_whitespace().map(str::to_string).collect();
+        let expd = words.expand(self.conf);
+        let opts = match_options(self.obj, &expd);
+
+        let query = parse_terms(&expd, &self.obj.regex, &self.obj.glob, &opts).and_then(|terms| {
+            Ok(Query {
+                terms,
+                content: parse_terms(&self.obj.content, &[], &[], &opts)?,
+            })
+        });
+        let query = match query {
+            Ok(q) => q,
+            // keep showing the last results while a regex is being typed
+            Err(e) => {
+                self.error = Some(e.to_string());
+                return;
+            }
+        };
+
+        let count = self.obj.count.unwrap_or(u32::MAX);
+        let hits = if query.terms.is_empty() && query.content.is_empty() {
+            Ok(self
+                .tree
+                .files
+                .iter()
+                .enumerate()
+                .take(count as usize)
+                .map(|(i, path)| Hit {
+                    path: path.clone(),
+                    score: Score::default(),
+                    meta: self.tree.meta.get(i).copied(),
+                    ranges: vec![],
+                    content: None,
+                })
+                .collect())
+        } else {
+            rank_in_tree(
+                self.tree,
+                None,
+                &query,
+                self.obj.strict,
+                count,
+                &opts,
+                &self.filters,
+            )
+        };
+
+        self.results = match hits {
+            Ok(hits) => hits,
+            Err(NoResult) => vec![],
+            Err(e) => {
+                self.error = Some(e.to_string());
+                return;
+            }
+        };
+
+        self.error = None;
+        self.terms = query.terms;
+        self.opts = opts;
+        self.selected = 0;
+        self.offset = 0;
+    }
+
+    fn current(&self) -> Option<&Hit> {
+        self.results.get(self.selected)
+    }
+
+    fn toggle(&mut self) {
+        let Some(hit) = self.current().cloned() else {
+            return;
+        };
+
+        match self.chosen.iter().position(|h| h.path == hit.path) {
+            Some(i) => {
+                self.chosen.remove(i);
+            }
+            None => self.chosen.push(hit),
+        }
+        self.down();
+    }
+
+    fn up(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    fn down(&mut self) {
+        self.selected = (self.selected + 1).min(self.results.len().saturating_sub(1));
+    }
+
+    /// Handles a key press, returns the chosen results once the picker is done.
+    fn key(&mut self, key: KeyEvent) -> Option<Result<Vec<Hit>, BFFError>> {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+
+        match key.code {
+            KeyCode::Esc => return Some(Err(Aborted)),
+            KeyCode::Char('c') if ctrl => return Some(Err(Aborted)),
+            KeyCode::Enter => {
+                if self.chosen.is_empty() {
+                    self.chosen.extend(self.current().cloned());
+                }
+                // with no results, Enter is ignored rather than printing nothing
+                if !self.chosen.is_empty() {
+                    return Some(Ok(std::mem::take(&mut self.chosen)));
+                }
+            }
+            KeyCode::Tab => self.toggle(),
+            KeyCode::Up => self.up(),
+            KeyCode::Char('p') if ctrl => self.up(),
+            KeyCode::Down => self.down(),
+            KeyCode::Char('n') if ctrl => self.down(),
+            KeyCode::Char('u') if ctrl => {
+                self.input.clear();
+                self.update();
+            }
+            KeyCode::Backspace => {
+                self.input.pop();
+                self.update();
+            }
+            KeyCode::Char(c) if !ctrl => {
+                self.input.push(c);
+                self.update();
+            }
+            _ => {}
+        }
+
+        None
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [input, body, status] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [results, preview] =
+            Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)])
+                .areas(body);
+
+        frame.render_widget(Paragraph::new(format!("> {}", self.input)), input);
+        frame.set_cursor_position(Position::new(
+            input.x + 2 + self.input.chars().count() as u16,
+            input.y,
+        ));
+
+        // only the results on screen are highlighted and drawn, there can be millions
+        let height = (results.height.saturating_sub(2) as usize).max(1);
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        } else if self.selected >= self.offset + height {
+            self.offset = self.selected + 1 - height;
+        }
+
+        let items: Vec<ListItem> = self
+            .results
+            .iter()
+            .skip(self.offset)
+            .take(height)
+            .map(|hit| {
+                let mark = if self.chosen.iter().any(|h| h.path == hit.path) {
+                    "+ "
+                } else {
+                    "  "
+                };
+                let mut line = highlighted(&hit.path, &self.terms, &self.opts, &self.palette);
+                line.spans.insert(0, Span::raw(mark).bold());
+                ListItem::new(line)
+            })
+            .collect();
+        let list = List::new(items)
+            .block(Block::bordered().title("results"))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        let mut state = ListState::default()
+            .with_selected((!self.results.is_empty()).then_some(self.selected - self.offset));
+        frame.render_stateful_widget(list, results, &mut state);
+
+        let tree = self
+            .current()
+            .map(|h| path_to_tree(&h.path, h.archive()).unwrap_or_default())
+            .unwrap_or_default();
+        frame.render_widget(
+            Paragraph::new(tree).block(Block::bordered().title("tree")),
+            preview,
+        );
+
+        let line = match &self.error {
+            Some(e) => Line::from(e.as_str()).red(),
+            None => Line::from(format!(
+                "{}/{} · {} selected · tab: select · enter: accept · esc: quit",
+                self.results.len(),
+                self.tree.files.len(),
+                self.chosen.len()
+            ))
+            .dim(),
+        };
+        frame.render_widget(Paragraph::new(line), status);
+    }
+}
+
+/// Splits `path` into spans, with the parts matched by each term in its own style.
+fn highlighted(
+    path: &str,
+    terms: &[Term],
+    opts: &MatchOptions,
+    palette: &Palette,
+) -> Line<'static> {
+    let mut spans = vec![];
+    let mut last = 0;
+
+    for (start, end, term) in find_term_ranges(path, terms, opts) {
+        spans.push(Span::raw(path[last..start].to_string()));
+        spans.push(Span::styled(
+            path[start..end].to_string(),
+            palette.term(term).to_tui(),
+        ));
+        last = end;
+    }
+    spans.push(Span::raw(path[last..].to_string()));
+
+    Line::from(spans)
+}
+
+/// Opens the interactive picker over the cached file tree, and returns the chosen results.
+pub fn pick(obj: &SearchArgs, conf: &TreeConfig) -> Result<Vec<Hit>, BFFError> {
+    info!("starting picker");
+
+    let tree_opts = TreeOptions {
+        show_hidden: obj.show_hidden,
+        archives: obj.archives,
+    };
+    let sum = checksum::gen_checksum()?;
+    let (tree, _) = cache::load_file_tree(&sum, obj.no_cache, &tree_opts)?;
+
+    let mut picker = Picker {
+        obj,
+        conf,
+        tree: &tree,
+        filters: Filters::new(
+            obj.newer.as_deref(),
+            obj.older.as_deref(),
+            &obj.size,
+            obj.executable,
+        )?,
+        palette: Palette::new(&conf.theme)?,
+        input: obj.terms.join(" "),
+        terms: vec![],
+        opts: MatchOptions::default(),
+        results: vec![],
+        selected: 0,
+        offset: 0,
+        chosen: vec![],
+        error: None,
+    };
+    picker.update();
+
+    let (_guard, mut terminal) = TerminalGuard::new()?;
+    loop {
+        terminal.draw(|frame| picker.draw(frame))?;
+
+        if let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+            && let Some(done) = picker.key(key)
+        {
+            return done;
+        }
+    }
+}
+
+/// Opens the interactive picker over the cached file tree, and prints the chosen paths.
+pub fn run_pick(obj: SearchArgs, conf: TreeConfig) -> Result<(), BFFError> {
+    let chosen: Vec<String> = pick(&obj, &conf)?.into_iter().map(|h| h.path).collect();
+
+    for path in &chosen {
+        println!("{path}");
+    }
+
+    if let Err(e) = Frecency::record(&chosen) {
+        warn!("unable to record visit: {e}");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+    use crate::config::schema::Theme;
+
+    #[test]
+    fn enter_needs_a_result() {
+        let obj = SearchArgs::parse_from(["pick"]);
+        let conf = TreeConfig::default();
+        let tree = FileTree {
+            files: Box::new(["./docs/report.docx".to_string()]),
+            ..Default::default()
+        };
+        let mut picker = Picker {
+            obj: &obj,
+            conf: &conf,
+            tree: &tree,
+            filters: Filters::default(),
+            palette: Palette::new(&Theme::default()).unwrap(),
+            input: "nothing".to_string(),
+            terms: vec![],
+            opts: MatchOptions::default(),
+            results: vec![],
+            selected: 0,
+            offset: 0,
+            chosen: vec![],
+            error: None,
+        };
+        let enter = KeyEvent::from(KeyCode::Enter);
+
+        picker.update();
+        assert!(picker.key(enter).is_none());
+
+        picker.key(KeyEvent::new(KeyCode::Char('u'), KeyModifiers::CONTROL));
+        let chosen = picker.key(enter).unwrap().unwrap();
+        assert_eq!(chosen[0].path, "./docs/report.docx");
+    }
+
+    #[test]
+    fn highlighted_spans_cover_the_path() {
+        let line = highlighted(
+            "./docs/report.docx",
+            &[Term::fuzzy("rep")],
+            &MatchOptions::default(),
+            &Palette::new(&Theme::default()).unwrap(),
+        );
+        let text: Vec<&str> = line.spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(text, ["./docs/", "rep", "ort.docx"]);
+    }
+}
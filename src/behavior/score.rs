@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+
+use crate::options::MatchOptions;
+use crate::parser::normalize::Normalized;
+use crate::parser::term::Term;
+use crate::parser::tokens::word_quality;
+
+/// How well a path matches the query, results are ranked by `matched` first.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Score {
+    /// Number of query terms found in the path.
+    pub matched: usize,
+    /// Tie breaker between paths matching the same number of terms.
+    pub bonus: u32,
+}
+
+pub fn score_path(path: &Normalized, terms: &[Term], matched: usize, opts: &MatchOptions) -> Score {
+    let mut bonus = 0;
+
+    if opts.ordered && matched > 1 {
+        bonus += order_bonus(path, terms);
+    }
+
+    bonus += terms
+        .iter()
+        .map(|t| term_quality(path, t, opts))
+        .sum::<u32>();
+
+    Score { matched, bonus }
+}
+
+/// With `--words` keywords are rated by [`word_quality`].
+fn term_quality(path: &Normalized, term: &Term, opts: &MatchOptions) -> u32 {
+    match term {
+        Term::Word(_) | Term::Fuzzy(_) | Term::Date(..) if opts.words => {
+            word_quality(path.words(), &term.ranges(path))
+        }
+        _ => 0,
+    }
+}
+
+/// Finds the best chain of terms placed on strictly increasing path components, in query order.
+///
+/// Every link in the chain is worth a point, and another one if the two components are adjacent,
+/// so `bff src main` prefers `./bff/src/main.rs` over `./main/src/bff.txt`.
+fn order_bonus(path: &Normalized, terms: &[Term]) -> u32 {
+    let positions: Vec<Vec<usize>> = terms
+        .iter()
+        .map(|t| {
+            let mut comps: Vec<usize> = t
+                .ranges(path)
+                .into_iter()
+                .map(|(start, _)| path.text[..start].matches('/').count())
+                .collect();
+            comps.dedup();
+            comps
+        })
+        .collect();
+
+    // best chain ending with some term at a given component
+    let mut chains: Vec<(usize, u32)> = vec![];
+    let mut best = 0;
+
+    for comps in positions {
+        let mut next = vec![];
+        for &c in &comps {
+            let bonus = chains
+                .iter()
+                .filter(|(prev, _)| *prev < c)
+                .map(|(prev, b)| b + 1 + u32::from(*prev + 1 == c))
+                .max()
+                .unwrap_or(0);
+            best = best.max(bonus);
+            next.push((c, bonus));
+        }
+        chains.extend(next);
+    }
+
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms(words: &[&str]) -> Vec<Term> {
+        words.iter().map(|w| Term::parse(w).unwrap()).collect()
+    }
+
+    #[test]
+    fn ordered_components_rank_higher() {
+        let q = terms(&["bff", "src", "main"]);
+        assert_eq!(order_bonus(&"./bff/src/main.rs".into(), &q), 4);
+        assert_eq!(order_bonus(&"./bff/x/src/y/main.rs".into(), &q), 2);
+        assert_eq!(order_bonus(&"./main/src/bff.txt".into(), &q), 0);
+    }
+
+    #[test]
+    fn same_component_is_not_ordered() {
+        let q = terms(&["src", "main"]);
+        assert_eq!(order_bonus(&"./src_main.rs".into(), &q), 0);
+    }
+}
@@ -1,146 +1,244 @@
-use itertools::Itertools;
-use log::info;
-
-use super::tree::path_to_tree;
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    env, fs,
+    path::Path,
+};
+
+use itertools::{Either, Itertools};
+use log::{info, warn};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::behavior::archives;
 use crate::behavior::cache;
-use crate::behavior::cache::write_cache_file;
+use crate::behavior::cache::{FileMeta, TreeOptions};
 use crate::behavior::checksum;
-use crate::behavior::strings;
+use crate::behavior::content::{search_content, ContentMatch, IgnoreRules};
+#[cfg(unix)]
+use crate::behavior::daemon;
+use crate::behavior::exec::{self, CommandTemplate};
+use crate::behavior::filters::Filters;
+use crate::behavior::frecency::Frecency;
+use crate::behavior::hyperlink::{use_hyperlinks, Hyperlinks};
+use crate::behavior::index;
+use crate::behavior::output::{use_color, Printer};
+use crate::behavior::pick::run_pick;
+use crate::behavior::score::{score_path, Score};
+use crate::behavior::strings::{self, TermRange};
+use crate::behavior::theme::Palette;
+use crate::behavior::watch::{self, Change, Events};
 use crate::cli::arg_parser::SearchArgs;
 use crate::cli::error::BFFError::{self, ArgumentCount, NoResult};
 use crate::config::schema::TreeConfig;
+use crate::options::{CaseMode, MatchOptions, OutputFormat, SortBy};
 use crate::parser::alias_expansion::ExpandAlias;
+use crate::parser::normalize::Normalized;
+use crate::parser::term::{parse_terms, Query, Term};
+
+/// A file matching the query.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Hit {
+    pub path: String,
+    pub score: Score,
+    pub meta: Option<FileMeta>,
+    /// Byte ranges of `path` matched by the terms, for highlighting.
+    pub ranges: Vec<TermRange>,
+    /// Where the `--content` terms were found.
+    pub content: Option<ContentMatch>,
+}
+
+impl Hit {
+    /// The archive this result is a member of, if it is one.
+    pub fn archive(&self) -> Option<&str> {
+        let meta = self.meta.as_ref()?;
+        archives::archive_of(&self.path, meta)
+    }
+
+    /// The file on disk holding this result, the archive for a member of one.
+    pub fn file(&self) -> &str {
+        self.archive().unwrap_or(&self.path)
+    }
+}
 
 pub fn search(
-    query: Vec<String>,
+    query: Query,
     strict: bool,
     count: u32,
-    show_hidden: bool,
     skip_cache: bool,
-) -> Result<Vec<String>, BFFError> {
+    tree_opts: &TreeOptions,
+    opts: &MatchOptions,
+    filters: &Filters,
+) -> Result<Vec<Hit>, BFFError> {
     info!(
         "begin {} search with terms {:?}",
         if strict { "strict" } else { "soft" },
         query
     );
 
-    let qlen = query.len();
-
-    if query.is_empty() {
+    if query.terms.is_empty() && query.content.is_empty() {
         return Err(ArgumentCount(0));
     }
 
     let sum = checksum::gen_checksum()?;
+    let (tree, changed) = cache::load_file_tree(&sum, skip_cache, tree_opts)?;
+    let candidates = index::load_index(&sum, &tree, changed, skip_cache, tree_opts)?
+        .and_then(|index| index.candidates(&query.terms, strict));
+
+    rank_in_tree(
+        &tree,
+        candidates.as_deref(),
+        &query,
+        strict,
+        count,
+        opts,
+        filters,
+    )
+}
 
-    let tree = if !checksum::check_cache(&sum, !show_hidden)? {
-        info!("cache is out of date");
-        let tree = cache::get_file_tree(show_hidden)?;
-        if !skip_cache {
-            info!("file tree changed, writing cache file");
-            write_cache_file(&sum, &tree, !show_hidden)?
-        };
-        tree
-    } else {
-        cache::read_cache_file()?
-    };
+/// Ranks the files of `tree`, or only `candidates` (indices into `tree.files`) when the
+/// index narrowed them down.
+pub fn rank_in_tree(
+    tree: &cache::FileTree,
+    candidates: Option<&[u32]>,
+    query: &Query,
+    strict: bool,
+    count: u32,
+    opts: &MatchOptions,
+    filters: &Filters,
+) -> Result<Vec<Hit>, BFFError> {
+    let qlen = query.terms.len();
 
-    let mut res: Vec<String> = vec![];
-    let mut full_match = false;
+    if query.terms.is_empty() && query.content.is_empty() {
+        return Err(ArgumentCount(0));
+    }
 
-    for n in (0..=qlen).rev() {
-        for leaf in &tree.files {
-            let match_size = largest_matching_subset_size(leaf, &query)?;
-            info!("checking file: {leaf}, {match_size} matches, n value of {n}");
+    let mut ranked: Vec<Hit> = vec![];
 
-            if match_size == n && n > 0 {
-                if strict && n == qlen {
-                    full_match = true;
-                }
+    let indices = match candidates {
+        Some(c) => Either::Left(c.iter().map(|&i| i as usize)),
+        None => Either::Right(0..tree.files.len()),
+    };
 
-                // Skip partial matches
-                if strict && n != qlen {
-                    continue;
-                }
+    for i in indices {
+        let Some(leaf) = tree.files.get(i) else {
+            continue;
+        };
+        let text = Normalized::new(leaf, opts);
+        let mtime = tree.meta.get(i).filter(|_| opts.mtime).map(|m| m.mtime);
+        let match_size = largest_matching_subset_size(&text, mtime, &query.terms)?;
+        info!("checking file: {leaf}, {match_size} matches");
+
+        // Skip partial matches, with only content terms every file is a candidate
+        if (match_size == 0 && qlen > 0) || (strict && match_size != qlen) {
+            continue;
+        }
 
-                info!("found file: {leaf}, {n} matches");
+        if !filters.matches(leaf, tree.meta.get(i)) {
+            info!("file filtered out: {leaf}");
+            continue;
+        }
 
-                res.push(strings::highlight_substr_plural(leaf, &query));
+        let score = score_path(&text, &query.terms, match_size, opts);
+        info!("found file: {leaf}, {score:?}");
 
-                if res.len() == count as usize {
-                    return Ok(res);
-                }
-            }
-        }
+        ranked.push(Hit {
+            path: leaf.clone(),
+            score,
+            meta: tree.meta.get(i).copied(),
+            ranges: vec![],
+            content: None,
+        });
     }
 
-    if strict && !full_match {
-        return Err(NoResult);
+    if !query.content.is_empty() {
+        ranked = match_contents(ranked, &query.content, strict, opts);
     }
 
-    if res.is_empty() {
-        Err(NoResult)
-    } else {
-        Ok(res)
-    }
-}
+    sort_ties(&mut ranked, opts.sort)?;
 
-#[allow(dead_code)]
-fn search_in_tree(
-    tree: &cache::FileTree,
-    query: Vec<String>,
-    strict: bool,
-    count: u32,
-) -> Result<Vec<String>, BFFError> {
-    let qlen = query.len();
+    // stable, so equally scored files keep the order from `sort_ties`
+    ranked.sort_by_key(|h| Reverse(h.score));
+    ranked.truncate(count as usize);
 
-    if query.is_empty() {
-        return Err(ArgumentCount(0));
+    for hit in &mut ranked {
+        hit.ranges = strings::find_term_ranges(&hit.path, &query.terms, opts);
+        if let Some(c) = &mut hit.content {
+            c.ranges = strings::find_term_ranges(&c.snippet, &query.content, opts);
+        }
     }
 
-    let mut res: Vec<String> = vec![];
-    let mut full_match = false;
-
-    for n in (0..=qlen).rev() {
-        for leaf in &tree.files {
-            let match_size = largest_matching_subset_size(leaf, &query)?;
-            info!("checking file: {leaf}, {match_size} matches, n value of {n}");
-
-            if match_size == n && n > 0 {
-                if strict && n == qlen {
-                    full_match = true;
-                }
+    if ranked.is_empty() {
+        Err(NoResult)
+    } else {
+        Ok(ranked)
+    }
+}
 
-                // Skip partial matches
-                if strict && n != qlen {
-                    continue;
-                }
+/// Greps the candidates for the content terms in parallel, dropping ignored files and
+/// files without a match. Content terms count towards the score like path terms.
+fn match_contents(hits: Vec<Hit>, content: &[Term], strict: bool, opts: &MatchOptions) -> Vec<Hit> {
+    let mut rules = IgnoreRules::default();
+    let mut hits: Vec<Hit> = hits
+        .into_iter()
+        .filter(|h| !rules.is_ignored(&h.path))
+        .collect();
+
+    hits.par_iter_mut().for_each(|hit| {
+        hit.content = search_content(&hit.path, content, opts);
+    });
 
-                info!("found file: {leaf}, {n} matches");
+    hits.into_iter()
+        .filter_map(|mut hit| {
+            let matched = hit.content.as_ref()?.matched;
+            if strict && matched != content.len() {
+                return None;
+            }
 
-                res.push(strings::highlight_substr_plural(leaf, &query));
+            info!("found content in file: {}, {matched} matches", hit.path);
+            hit.score.matched += matched;
+            Some(hit)
+        })
+        .collect()
+}
 
-                if res.len() == count as usize {
-                    return Ok(res);
-                }
-            }
+/// Orders hits by the `--sort` key, which decides between files with the same score.
+/// Sorting by score alone keeps the tree order.
+fn sort_ties(hits: &mut [Hit], sort: SortBy) -> Result<(), BFFError> {
+    if matches!(sort, SortBy::Mtime | SortBy::Size) {
+        for hit in hits.iter_mut().filter(|h| h.meta.is_none()) {
+            hit.meta = fs::metadata(&hit.path)
+                .ok()
+                .map(|md| FileMeta::from_metadata(&md));
         }
     }
 
-    if strict && !full_match {
-        return Err(NoResult);
+    match sort {
+        SortBy::Score => {}
+        SortBy::Frecency => {
+            let store = Frecency::load()?;
+            hits.sort_by_cached_key(|h| Reverse(store.score(&h.path)));
+        }
+        SortBy::Mtime => hits.sort_by_key(|h| Reverse(h.meta.map_or(0, |m| m.mtime))),
+        SortBy::Path => hits.sort_by(|a, b| a.path.cmp(&b.path)),
+        SortBy::Size => hits.sort_by_key(|h| Reverse(h.meta.map_or(0, |m| m.size))),
+        SortBy::Depth => hits.sort_by_key(|h| h.path.matches('/').count()),
     }
 
-    if res.is_empty() {
-        Err(NoResult)
-    } else {
-        Ok(res)
-    }
+    Ok(())
 }
 
-pub fn largest_matching_subset_size(test: &str, query: &[String]) -> Result<usize, BFFError> {
+pub fn largest_matching_subset_size(
+    test: &Normalized,
+    mtime: Option<i64>,
+    query: &[Term],
+) -> Result<usize, BFFError> {
     for size in (1..=query.len()).rev() {
         for subset in query.iter().combinations(size) {
-            if subset.iter().all(|q| test.contains(*q)) {
+            if subset
+                .iter()
+                .all(|q| q.is_match(test) || mtime.is_some_and(|m| q.matches_mtime(m)))
+            {
                 return Ok(size);
             }
         }
@@ -149,32 +247,356 @@ pub fn largest_matching_subset_size(test: &str, query: &[String]) -> Result<usiz
     Ok(0)
 }
 
+/// Runs the search, through the daemon for the working directory when one is running.
+/// `expd` are the terms after alias expansion, `query` the same terms parsed.
+pub fn find(
+    obj: &SearchArgs,
+    expd: Vec<String>,
+    query: &Query,
+    opts: &MatchOptions,
+    filters: &Filters,
+    count: u32,
+) -> Result<Vec<Hit>, BFFError> {
+    let tree_opts = TreeOptions {
+        show_hidden: obj.show_hidden,
+        archives: obj.archives,
+    };
+
+    #[cfg(unix)]
+    let from_daemon = daemon::query(&daemon::Request {
+        terms: expd,
+        regex: obj.regex.clone(),
+        glob: obj.glob.clone(),
+        content: obj.content.clone(),
+        strict: obj.strict,
+        count,
+        opts: opts.clone(),
+        filters: filters.clone(),
+        tree_opts,
+    })?;
+    #[cfg(not(unix))]
+    let from_daemon = {
+        let _ = expd;
+        None
+    };
+
+    match from_daemon {
+        Some(hits) => Ok(hits),
+        None => search(
+            query.clone(),
+            obj.strict,
+            count,
+            obj.no_cache,
+            &tree_opts,
+            opts,
+            filters,
+        ),
+    }
+}
+
 pub fn run_search(obj: SearchArgs, conf: TreeConfig) -> Result<(), BFFError> {
     info!("searching for files");
 
-    let expd = obj.terms.expand(conf);
+    if obj.interactive {
+        return run_pick(obj, conf);
+    }
+
+    colored::control::set_override(use_color(obj.color));
+    let palette = Palette::new(&conf.theme)?;
+
+    let expd = obj.terms.expand(&conf);
 
     info!("before alias expansion: {:?}", obj.terms);
     info!("after alias expansion: {expd:?}");
 
+    let opts = match_options(&obj, &expd);
+    let query = Query {
+        terms: parse_terms(&expd, &obj.regex, &obj.glob, &opts)?,
+        content: parse_terms(&obj.content, &[], &[], &opts)?,
+    };
+
+    let filters = Filters::new(
+        obj.newer.as_deref(),
+        obj.older.as_deref(),
+        &obj.size,
+        obj.executable,
+    )?;
+
     let count = obj.count.unwrap_or(if obj.all { u32::MAX } else { 1 });
-    let ss = search(expd, obj.strict, count, obj.show_hidden, obj.no_cache)?;
-    for s in ss {
-        println!("{s}");
-        if obj.tree {
-            println!("{}", path_to_tree(&s)?);
+
+    // watching starts before searching so no file is missed in between
+    let watcher = if obj.watch {
+        Some(watch::watch(&env::current_dir()?.canonicalize()?)?)
+    } else {
+        None
+    };
+
+    let hits = find(&obj, expd, &query, &opts, &filters, count);
+    let mut hits = match hits {
+        // the files a watch waits for usually don't exist yet
+        Err(NoResult) if obj.watch => vec![],
+        hits => hits?,
+    };
+    if obj.dir_of {
+        hits = dirs_of(hits);
+    }
+
+    if !obj.exec.is_empty() || !obj.exec_batch.is_empty() {
+        // members of archives can't be handed to a command
+        let paths: Vec<&str> = hits
+            .iter()
+            .filter(|h| match h.archive() {
+                Some(_) => {
+                    warn!("skipping archive member: {}", h.path);
+                    false
+                }
+                None => true,
+            })
+            .map(|h| h.path.as_str())
+            .collect();
+        // directories aren't visits of a file
+        if !obj.dir_of && !paths.is_empty() {
+            record_visits(&paths);
+        }
+        return if obj.exec.is_empty() {
+            exec::exec_batch(&CommandTemplate::new(&obj.exec_batch), &paths)
+        } else {
+            exec::exec_each(&CommandTemplate::new(&obj.exec), &paths, obj.threads)
         };
     }
 
+    let printer = Printer {
+        format: if obj.print0 {
+            OutputFormat::Null
+        } else {
+            obj.format
+        },
+        query: &query,
+        opts: &opts,
+        tree: obj.tree,
+        palette: &palette,
+        links: if use_hyperlinks(obj.hyperlink) {
+            Some(Hyperlinks::new()?)
+        } else {
+            None
+        },
+    };
+    printer.print_all(&hits)?;
+
+    if let Some((_watcher, rx)) = watcher {
+        let shown = hits
+            .into_iter()
+            .map(|h| (h.path, h.meta.and_then(|m| m.archive)))
+            .collect();
+        watch_search(&rx, &obj, &printer, &filters, shown)?;
+    }
+
     Ok(())
 }
 
+/// The directories of the results, each one where it first comes up. Members of an
+/// archive are in the directory of the archive.
+fn dirs_of(hits: Vec<Hit>) -> Vec<Hit> {
+    let mut seen = HashSet::new();
+
+    hits.into_iter()
+        .filter_map(|mut hit| {
+            let dir = match Path::new(hit.file())
+                .parent()
+                .map(|d| d.display().to_string())
+            {
+                Some(d) if !d.is_empty() => d,
+                _ => ".".to_string(),
+            };
+            if !seen.insert(dir.clone()) {
+                return None;
+            }
+
+            // the directory is a prefix of the path, so the matches inside it still apply
+            hit.ranges.retain_mut(|r| {
+                r.1 = r.1.min(dir.len());
+                r.0 < r.1
+            });
+            hit.path = dir;
+            hit.content = None;
+            // a directory is never inside an archive
+            hit.meta = hit.meta.map(|m| FileMeta { archive: None, ..m });
+            Some(hit)
+        })
+        .collect()
+}
+
+/// Counts the results a command is run on as visited, unlike printed results which may
+/// go nowhere.
+fn record_visits(paths: &[&str]) {
+    if let Err(e) = Frecency::record(paths) {
+        warn!("unable to record visits: {e}");
+    }
+}
+
+/// The matching options from the flags, `terms` decide the case sensitivity with
+/// `--case smart`.
+pub fn match_options(obj: &SearchArgs, terms: &[String]) -> MatchOptions {
+    let ignore_case = match obj.case {
+        CaseMode::Sensitive => false,
+        CaseMode::Insensitive => true,
+        CaseMode::Smart => !terms
+            .iter()
+            .chain(&obj.regex)
+            .chain(&obj.glob)
+            .chain(&obj.content)
+            .any(|t| t.chars().any(char::is_uppercase)),
+    };
+
+    MatchOptions {
+        ordered: obj.ordered,
+        words: obj.words,
+        ignore_case,
+        fold: obj.fold,
+        mtime: obj.mtime,
+        sort: obj.sort,
+    }
+}
+
+/// Prints files that start matching the query as the filesystem changes, `shown` holds
+/// the files printed so far, with the length of their archive's path for members of one.
+fn watch_search(
+    rx: &Events,
+    obj: &SearchArgs,
+    printer: &Printer,
+    filters: &Filters,
+    mut shown: HashMap<String, Option<u32>>,
+) -> Result<(), BFFError> {
+    let root = env::current_dir()?.canonicalize()?;
+
+    while let Some(changed) = watch::next_batch(rx) {
+        for change in watch::changes(&changed, &root, obj.show_hidden) {
+            for update in updates(change, obj, printer, filters, &mut shown)? {
+                match update {
+                    Update::Found(hit) => printer.print(&hit)?,
+                    Update::Gone(path) if obj.removed => printer.print_removed(&path)?,
+                    Update::Gone(_) => {}
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// What a change on disk means for the results of a `--watch` search.
+#[derive(Debug)]
+enum Update {
+    /// A file that started matching.
+    Found(Hit),
+    /// A file printed before that is gone.
+    Gone(String),
+}
+
+/// Matches a single change against the query, `shown` is kept up to date so every file
+/// is found once and only files that were found can be gone.
+fn updates(
+    change: Change,
+    obj: &SearchArgs,
+    printer: &Printer,
+    filters: &Filters,
+    shown: &mut HashMap<String, Option<u32>>,
+) -> Result<Vec<Update>, BFFError> {
+    match change {
+        Change::Added(path, meta) => {
+            let mut tree = cache::FileTree {
+                files: Box::new([path]),
+                meta: Box::new([meta]),
+                ..Default::default()
+            };
+            if obj.archives {
+                archives::index_archives(&mut tree, None);
+            }
+
+            let hits = match rank_in_tree(
+                &tree,
+                None,
+                printer.query,
+                obj.strict,
+                u32::MAX,
+                printer.opts,
+                filters,
+            ) {
+                Err(NoResult) => return Ok(vec![]),
+                hits => hits?,
+            };
+
+            Ok(hits
+                .into_iter()
+                .filter(|hit| {
+                    let archive = hit.meta.and_then(|m| m.archive);
+                    shown.insert(hit.path.clone(), archive).is_none()
+                })
+                .map(Update::Found)
+                .collect())
+        }
+        Change::Removed(path) => {
+            let dir = format!("{path}/");
+            let gone: Vec<String> = shown
+                .iter()
+                .filter(|(s, archive)| {
+                    **s == path
+                        || s.starts_with(&dir)
+                        || (**archive == Some(path.len() as u32) && s.starts_with(&path))
+                })
+                .map(|(s, _)| s.clone())
+                .collect();
+
+            for p in &gone {
+                shown.remove(p);
+            }
+            Ok(gone.into_iter().map(Update::Gone).collect())
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+
+    use clap::Parser;
     use once_cell::sync::Lazy;
 
-    use super::search_in_tree;
-    use crate::behavior::cache::FileTree;
+    use super::{rank_in_tree, updates, Update};
+    use crate::behavior::cache::{FileMeta, FileTree};
+    use crate::behavior::filters::Filters;
+    use crate::behavior::index::TrigramIndex;
+    use crate::behavior::output::Printer;
+    use crate::behavior::theme::Palette;
+    use crate::behavior::watch::Change;
+    use crate::cli::arg_parser::SearchArgs;
+    use crate::cli::error::BFFError;
+    use crate::config::schema::Theme;
+    use crate::options::{MatchOptions, OutputFormat, SortBy};
+    use crate::parser::term::{parse_terms, Query, Term};
+
+    /// Runs a search and renders the results with their highlighted ranges.
+    fn search_in_tree(
+        tree: &FileTree,
+        query: Vec<Term>,
+        strict: bool,
+        count: u32,
+        opts: &MatchOptions,
+        filters: &Filters,
+    ) -> Result<Vec<String>, BFFError> {
+        // one color for every term keeps the expected strings readable
+        let palette = Palette::new(&Theme {
+            matches: vec!["bold yellow".to_string()],
+            ..Default::default()
+        })?;
+
+        Ok(
+            rank_in_tree(tree, None, &query.into(), strict, count, opts, filters)?
+                .into_iter()
+                .map(|hit| palette.paint_path(&hit.path, &hit.ranges))
+                .collect(),
+        )
+    }
 
     static FILES: Lazy<Box<[String]>> = Lazy::new(|| {
         r#"
@@ -206,14 +628,18 @@ mod tests {
 
     #[test]
     fn strict_search() {
+        colored::control::set_override(true);
         let tree: FileTree = FileTree {
             files: FILES.clone(),
+            ..Default::default()
         };
         let r = search_in_tree(
             &tree,
-            vec!["t".to_string(), "s".to_string(), "o".to_string()],
+            vec![Term::fuzzy("t"), Term::fuzzy("s"), Term::fuzzy("o")],
             true,
             3,
+            &MatchOptions::default(),
+            &Filters::default(),
         )
         .unwrap();
 
@@ -228,19 +654,23 @@ mod tests {
 
     #[test]
     fn soft_search() {
+        colored::control::set_override(true);
         let tree: FileTree = FileTree {
             files: FILES.clone(),
+            ..Default::default()
         };
         let r = search_in_tree(
             &tree,
             vec![
-                "final".to_string(),
-                "back".to_string(),
-                "docs".to_string(),
-                "read".to_string(),
+                Term::fuzzy("final"),
+                Term::fuzzy("back"),
+                Term::fuzzy("docs"),
+                Term::fuzzy("read"),
             ],
             false,
             3,
+            &MatchOptions::default(),
+            &Filters::default(),
         )
         .unwrap();
 
@@ -252,4 +682,317 @@ mod tests {
 
         assert_eq!(r, p);
     }
+
+    #[test]
+    fn mixed_term_search() {
+        colored::control::set_override(true);
+        let tree: FileTree = FileTree {
+            files: FILES.clone(),
+            ..Default::default()
+        };
+        let r = search_in_tree(
+            &tree,
+            vec![
+                Term::fuzzy("backup"),
+                Term::parse(r"/_\d{4}\./").unwrap(),
+                Term::glob("*.tar.gz").unwrap(),
+            ],
+            false,
+            3,
+            &MatchOptions::default(),
+            &Filters::default(),
+        )
+        .unwrap();
+
+        let p: Vec<String> = vec![
+            "/\u{1b}[1;33mbackup\u{1b}[0ms/\u{1b}[1;33mbackup_2023.tar.gz\u{1b}[0m".to_string(),
+            "/spreadsheets/budget\u{1b}[1;33m_2024.\u{1b}[0mxlsx".to_string(),
+            "/\u{1b}[1;33mbackup\u{1b}[0ms/archive_\u{1b}[1;33mbackup\u{1b}[0m.zip".to_string(),
+        ];
+
+        assert_eq!(r, p);
+    }
+
+    #[test]
+    fn word_search() {
+        colored::control::set_override(true);
+        let tree: FileTree = FileTree {
+            files: FILES.clone(),
+            ..Default::default()
+        };
+        let opts = MatchOptions {
+            words: true,
+            ..Default::default()
+        };
+        let r = search_in_tree(
+            &tree,
+            vec![Term::Word("log".to_string())],
+            false,
+            2,
+            &opts,
+            &Filters::default(),
+        )
+        .unwrap();
+
+        let p: Vec<String> = vec![
+            "/\u{1b}[1;33mlog\u{1b}[0ms/error_\u{1b}[1;33mlog\u{1b}[0m_20250718.\u{1b}[1;33mlog\u{1b}[0m".to_string(),
+            "/presentations/\u{1b}[1;33mlog\u{1b}[0mo_design.ai".to_string(),
+        ];
+
+        assert_eq!(r, p);
+
+        let r = search_in_tree(
+            &tree,
+            vec![Term::Word("mm".to_string())],
+            false,
+            2,
+            &opts,
+            &Filters::default(),
+        )
+        .unwrap();
+
+        let p: Vec<String> = vec![
+            "/docs/\u{1b}[1;33mm\u{1b}[0meeting_\u{1b}[1;33mm\u{1b}[0minutes_07-15.pdf".to_string(),
+        ];
+
+        assert_eq!(r, p);
+
+        // lowercasing the path must not merge camelCase words
+        let tree = FileTree {
+            files: vec!["/docs/meetingMinutes.pdf".to_string()].into(),
+            ..Default::default()
+        };
+        let opts = MatchOptions {
+            ignore_case: true,
+            ..opts
+        };
+        let r = search_in_tree(
+            &tree,
+            parse_terms(&["MM".to_string()], &[], &[], &opts).unwrap(),
+            false,
+            2,
+            &opts,
+            &Filters::default(),
+        )
+        .unwrap();
+
+        let p: Vec<String> =
+            vec!["/docs/\u{1b}[1;33mm\u{1b}[0meeting\u{1b}[1;33mM\u{1b}[0minutes.pdf".to_string()];
+
+        assert_eq!(r, p);
+    }
+
+    #[test]
+    fn folded_search() {
+        colored::control::set_override(true);
+        let tree: FileTree = FileTree {
+            files: Box::new([
+                "/docs/re\u{301}sume\u{301}.pdf".to_string(),
+                "/docs/R\u{e9}sum\u{e9}_old.pdf".to_string(),
+            ]),
+            ..Default::default()
+        };
+        let opts = MatchOptions {
+            fold: true,
+            ignore_case: true,
+            ..Default::default()
+        };
+        let query = vec![Term::fuzzy("résumé").normalized(&opts).unwrap()];
+        let r = search_in_tree(&tree, query, false, 2, &opts, &Filters::default()).unwrap();
+
+        let p: Vec<String> = vec![
+            "/docs/\u{1b}[1;33mre\u{301}sume\u{301}\u{1b}[0m.pdf".to_string(),
+            "/docs/\u{1b}[1;33mR\u{e9}sum\u{e9}\u{1b}[0m_old.pdf".to_string(),
+        ];
+
+        assert_eq!(r, p);
+    }
+
+    #[test]
+    fn date_search() {
+        colored::control::set_override(true);
+        let tree: FileTree = FileTree {
+            files: Box::new([
+                "./statements/01-26.txt".to_string(),
+                "./payment/checkstubs/may/05-14-25.pdf".to_string(),
+                "./statements/05-27.txt".to_string(),
+            ]),
+            ..Default::default()
+        };
+        let opts = MatchOptions::default();
+        let query = parse_terms(&["may".to_string()], &[], &[], &opts).unwrap();
+        let r = search_in_tree(&tree, query, false, 3, &opts, &Filters::default()).unwrap();
+
+        let p: Vec<String> = vec![
+            "./payment/checkstubs/\u{1b}[1;33mmay\u{1b}[0m/\u{1b}[1;33m05-14-25\u{1b}[0m.pdf"
+                .to_string(),
+            "./statements/\u{1b}[1;33m05-27\u{1b}[0m.txt".to_string(),
+        ];
+
+        assert_eq!(r, p);
+    }
+
+    #[test]
+    fn ties_follow_sort_key() {
+        let tree: FileTree = FileTree {
+            files: Box::new([
+                "./b/deep/notes.txt".to_string(),
+                "./notes_a.txt".to_string(),
+                "./a/notes.md".to_string(),
+            ]),
+            ..Default::default()
+        };
+        let paths = |sort: SortBy| -> Vec<String> {
+            let opts = MatchOptions {
+                sort,
+                ..Default::default()
+            };
+            rank_in_tree(
+                &tree,
+                None,
+                &vec![Term::fuzzy("notes")].into(),
+                false,
+                3,
+                &opts,
+                &Filters::default(),
+            )
+            .unwrap()
+            .into_iter()
+            .map(|h| h.path)
+            .collect()
+        };
+
+        assert_eq!(
+            paths(SortBy::Path),
+            vec!["./a/notes.md", "./b/deep/notes.txt", "./notes_a.txt"]
+        );
+        assert_eq!(
+            paths(SortBy::Depth),
+            vec!["./notes_a.txt", "./a/notes.md", "./b/deep/notes.txt"]
+        );
+    }
+
+    #[test]
+    fn dirs_of_results_keep_their_matches() {
+        let tree = FileTree {
+            files: FILES.clone(),
+            ..Default::default()
+        };
+        let hits = rank_in_tree(
+            &tree,
+            None,
+            &vec![Term::fuzzy("backup")].into(),
+            false,
+            u32::MAX,
+            &MatchOptions::default(),
+            &Filters::default(),
+        )
+        .unwrap();
+
+        let dirs = super::dirs_of(hits);
+        assert_eq!(dirs.len(), 1);
+        assert_eq!(dirs[0].path, "/backups");
+        assert_eq!(dirs[0].ranges, [(1, 7, 0)]);
+    }
+
+    #[test]
+    fn indexed_search_matches_scan() {
+        let tree = FileTree {
+            files: FILES.clone(),
+            ..Default::default()
+        };
+        let index = TrigramIndex::build(&tree);
+        let opts = MatchOptions::default();
+
+        for strict in [false, true] {
+            let query: Query = vec![Term::fuzzy("final"), Term::fuzzy("docs")].into();
+            let candidates = index.candidates(&query.terms, strict);
+            assert!(candidates.is_some());
+
+            let paths = |candidates: Option<&[u32]>| -> Vec<String> {
+                rank_in_tree(
+                    &tree,
+                    candidates,
+                    &query,
+                    strict,
+                    u32::MAX,
+                    &opts,
+                    &Filters::default(),
+                )
+                .unwrap()
+                .into_iter()
+                .map(|h| h.path)
+                .collect()
+            };
+            assert_eq!(paths(candidates.as_deref()), paths(None));
+        }
+    }
+
+    #[test]
+    fn stale_candidates_are_skipped() {
+        let tree = FileTree {
+            files: FILES[..3].into(),
+            ..Default::default()
+        };
+        let query: Query = vec![Term::fuzzy("docs")].into();
+
+        // ids from an index built for a larger tree
+        let hits = rank_in_tree(
+            &tree,
+            Some(&[1, 7, 12]),
+            &query,
+            false,
+            u32::MAX,
+            &MatchOptions::default(),
+            &Filters::default(),
+        )
+        .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].path, "/docs/project_notes.txt");
+    }
+
+    #[test]
+    fn watched_changes_print_matches_once() {
+        colored::control::set_override(true);
+        let obj = SearchArgs::parse_from(["search", "--watch", "--removed", "notes"]);
+        let query = parse_terms(&obj.terms, &[], &[], &MatchOptions::default())
+            .unwrap()
+            .into();
+        let palette = Palette::new(&Theme {
+            matches: vec!["bold yellow".to_string()],
+            ..Default::default()
+        })
+        .unwrap();
+        let printer = Printer {
+            format: OutputFormat::Plain,
+            query: &query,
+            opts: &MatchOptions::default(),
+            tree: false,
+            palette: &palette,
+            links: None,
+        };
+
+        let mut shown = HashMap::new();
+        let mut feed = |change: Change| -> Vec<String> {
+            updates(change, &obj, &printer, &Filters::default(), &mut shown)
+                .unwrap()
+                .into_iter()
+                .map(|u| match u {
+                    Update::Found(hit) => hit.path,
+                    Update::Gone(path) => printer.removed_line(&path).unwrap().unwrap(),
+                })
+                .collect()
+        };
+        let added = |path: &str| Change::Added(path.to_string(), FileMeta::default());
+
+        assert_eq!(feed(added("./docs/notes.txt")), vec!["./docs/notes.txt"]);
+        assert!(feed(added("./docs/notes.txt")).is_empty());
+        assert!(feed(added("./docs/todo.txt")).is_empty());
+        assert!(feed(Change::Removed("./docs/todo.txt".to_string())).is_empty());
+        assert_eq!(
+            feed(Change::Removed("./docs".to_string())),
+            vec!["- ./docs/\u{1b}[1;33mnotes\u{1b}[0m.txt"]
+        );
+        assert_eq!(feed(added("./docs/notes.txt")), vec!["./docs/notes.txt"]);
+    }
 }
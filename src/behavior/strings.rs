@@ -1,66 +1,62 @@
-use std::cmp::max;
-
-use colored::Colorize;
-use log::info;
-
-pub fn find_pattern_indices(full_string: &str, substrs: &Vec<String>) -> Vec<(usize, usize)> {
-    if substrs.is_empty() {
+use crate::options::MatchOptions;
+use crate::parser::normalize::Normalized;
+use crate::parser::term::Term;
+
+/// A byte range and the index of the term that matched it.
+pub type TermRange = (usize, usize, usize);
+
+/// Finds the byte ranges of `full_string` matched by each term.
+///
+/// Terms are matched against the normalized string, the ranges are mapped back onto
+/// grapheme boundaries of `full_string` so they are always safe to slice with. Ranges
+/// never overlap: where two terms match the same text, the one starting first keeps it.
+pub fn find_term_ranges(full_string: &str, terms: &[Term], opts: &MatchOptions) -> Vec<TermRange> {
+    if terms.is_empty() {
         return vec![];
     }
 
-    let mut indices: Vec<(usize, usize)> = vec![];
-    for substr in substrs {
-        let matches: Box<[(usize, &str)]> = full_string.match_indices(substr).collect();
+    let normalized = Normalized::new(full_string, opts);
 
-        for m in matches {
-            indices.push((m.0, m.0 + substr.len()));
-        }
+    let mut ranges: Vec<TermRange> = vec![];
+    for (i, term) in terms.iter().enumerate() {
+        ranges.extend(term.ranges(&normalized).into_iter().map(|r| {
+            let (start, end) = normalized.original_range(r);
+            (start, end, i)
+        }));
     }
 
-    sort_and_merge(indices)
+    split_overlaps(ranges)
 }
 
-fn sort_and_merge(mut ranges: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
-    if ranges.is_empty() {
-        return ranges;
-    }
-
-    ranges.sort_by_key(|r| r.0);
-    let mut merged = Vec::new();
-    let mut current = ranges[0];
-
-    for &range in &ranges[1..] {
-        if range.0 <= current.1 {
-            current.1 = max(current.1, range.1);
-        } else {
-            merged.push(current);
-            current = range;
+fn split_overlaps(mut ranges: Vec<TermRange>) -> Vec<TermRange> {
+    ranges.sort();
+
+    let mut split: Vec<TermRange> = Vec::with_capacity(ranges.len());
+    for (start, end, term) in ranges {
+        match split.last().copied() {
+            Some((_, last_end, last_term)) if last_term == term && start <= last_end => {
+                let last = split.len() - 1;
+                split[last].1 = last_end.max(end);
+            }
+            Some((_, last_end, _)) if end > last_end => {
+                split.push((start.max(last_end), end, term))
+            }
+            Some(_) => {}
+            None => split.push((start, end, term)),
         }
     }
-    merged.push(current);
-    merged
+    split
 }
 
-pub fn highlight_substr_plural(s: &str, substrs: &Vec<String>) -> String {
-    info!("highlighting patterns: {substrs:?}, in string: \"{s}\"");
-
-    let ranges = find_pattern_indices(s, substrs);
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    let mut result = String::new();
-    let mut last = 0;
-
-    for (start, end) in ranges {
-        if start > last {
-            result.push_str(&s[last..start]);
-        }
-
-        result.push_str(&s[start..end].yellow().bold().to_string());
-        last = end;
+    #[test]
+    fn overlapping_ranges_keep_their_first_term() {
+        assert_eq!(
+            split_overlaps(vec![(4, 9, 1), (0, 3, 0), (2, 6, 0), (5, 7, 2), (9, 10, 2)]),
+            [(0, 6, 0), (6, 9, 1), (9, 10, 2)]
+        );
     }
-
-    if last < s.len() {
-        result.push_str(&s[last..]);
-    }
-
-    result
 }
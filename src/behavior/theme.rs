@@ -0,0 +1,265 @@
+use std::str::FromStr;
+
+use colored::{Color, Colorize};
+use ratatui::style as tui;
+
+use crate::behavior::strings::TermRange;
+use crate::cli::error::BFFError::{self, InvalidStyle};
+use crate::config::schema::Theme;
+
+/// A color and text attributes, parsed from a style such as `"bold bright blue"`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Style {
+    color: Option<Color>,
+    bold: bool,
+    dimmed: bool,
+    italic: bool,
+    underline: bool,
+}
+
+impl FromStr for Style {
+    type Err = BFFError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut style = Style::default();
+        let mut color = vec![];
+
+        for word in s.split_whitespace().map(str::to_lowercase) {
+            match word.as_str() {
+                "bold" => style.bold = true,
+                "dim" | "dimmed" => style.dimmed = true,
+                "italic" => style.italic = true,
+                "underline" => style.underline = true,
+                hex if hex.starts_with('#') => {
+                    style.color = Some(parse_hex(hex).ok_or_else(|| InvalidStyle(s.into()))?)
+                }
+                _ => color.push(word),
+            }
+        }
+
+        if !color.is_empty() {
+            style.color = Some(
+                color
+                    .join(" ")
+                    .parse()
+                    .map_err(|_| InvalidStyle(s.into()))?,
+            );
+        }
+
+        Ok(style)
+    }
+}
+
+fn parse_hex(hex: &str) -> Option<Color> {
+    let hex = hex.strip_prefix('#')?;
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+
+    match hex.len() {
+        6 => Some(Color::TrueColor {
+            r: channel(0)?,
+            g: channel(2)?,
+            b: channel(4)?,
+        }),
+        _ => None,
+    }
+}
+
+impl Style {
+    /// Applies the style to `s`. Whether escape codes are emitted at all is decided once
+    /// for the whole run, see `--color`.
+    pub fn paint(&self, s: &str) -> String {
+        if s.is_empty() {
+            return String::new();
+        }
+
+        let mut painted = s.normal();
+        if let Some(color) = self.color {
+            painted = painted.color(color);
+        }
+        if self.bold {
+            painted = painted.bold();
+        }
+        if self.dimmed {
+            painted = painted.dimmed();
+        }
+        if self.italic {
+            painted = painted.italic();
+        }
+        if self.underline {
+            painted = painted.underline();
+        }
+        painted.to_string()
+    }
+
+    /// The same style for the interactive picker.
+    pub fn to_tui(self) -> tui::Style {
+        let mut style = tui::Style::new();
+        if let Some(color) = self.color {
+            style = style.fg(tui_color(color));
+        }
+        for (on, modifier) in [
+            (self.bold, tui::Modifier::BOLD),
+            (self.dimmed, tui::Modifier::DIM),
+            (self.italic, tui::Modifier::ITALIC),
+            (self.underline, tui::Modifier::UNDERLINED),
+        ] {
+            if on {
+                style = style.add_modifier(modifier);
+            }
+        }
+        style
+    }
+}
+
+fn tui_color(color: Color) -> tui::Color {
+    match color {
+        Color::Black => tui::Color::Black,
+        Color::Red => tui::Color::Red,
+        Color::Green => tui::Color::Green,
+        Color::Yellow => tui::Color::Yellow,
+        Color::Blue => tui::Color::Blue,
+        Color::Magenta => tui::Color::Magenta,
+        Color::Cyan => tui::Color::Cyan,
+        Color::White => tui::Color::Gray,
+        Color::BrightBlack => tui::Color::DarkGray,
+        Color::BrightRed => tui::Color::LightRed,
+        Color::BrightGreen => tui::Color::LightGreen,
+        Color::BrightYellow => tui::Color::LightYellow,
+        Color::BrightBlue => tui::Color::LightBlue,
+        Color::BrightMagenta => tui::Color::LightMagenta,
+        Color::BrightCyan => tui::Color::LightCyan,
+        Color::BrightWhite => tui::Color::White,
+        Color::TrueColor { r, g, b } => tui::Color::Rgb(r, g, b),
+    }
+}
+
+/// The parsed `[theme]` of the config.
+#[derive(Debug, Clone)]
+pub struct Palette {
+    matches: Vec<Style>,
+    directory: Style,
+    basename: Style,
+    tree: Style,
+}
+
+impl Palette {
+    pub fn new(theme: &Theme) -> Result<Self, BFFError> {
+        Ok(Palette {
+            matches: theme
+                .matches
+                .iter()
+                .map(|s| s.parse())
+                .collect::<Result<_, _>>()?,
+            directory: theme.directory.parse()?,
+            basename: theme.basename.parse()?,
+            tree: theme.tree.parse()?,
+        })
+    }
+
+    /// The style of the text matched by the `i`th term.
+    pub fn term(&self, i: usize) -> Style {
+        match self.matches.len() {
+            0 => Style::default(),
+            n => self.matches[i % n],
+        }
+    }
+
+    /// Highlights the ranges of `s` in the style of the term that matched them.
+    pub fn paint(&self, s: &str, ranges: &[TermRange]) -> String {
+        self.render(s, ranges, 0, Style::default(), Style::default())
+    }
+
+    /// Highlights the ranges of `path`, and styles the rest of its directory and basename.
+    pub fn paint_path(&self, path: &str, ranges: &[TermRange]) -> String {
+        let basename = path.rfind('/').map_or(0, |i| i + 1);
+        self.render(path, ranges, basename, self.directory, self.basename)
+    }
+
+    /// Styles every line of a tree drawn by `path_to_tree`.
+    pub fn paint_tree(&self, tree: &str) -> String {
+        tree.lines()
+            .map(|line| self.tree.paint(line) + "\n")
+            .collect()
+    }
+
+    /// Text before `split` that isn't matched gets the `before` style, the rest `after`.
+    fn render(
+        &self,
+        s: &str,
+        ranges: &[TermRange],
+        split: usize,
+        before: Style,
+        after: Style,
+    ) -> String {
+        let mut out = String::new();
+        let plain = |from: usize, to: usize, out: &mut String| {
+            if from < split {
+                out.push_str(&before.paint(&s[from..to.min(split)]));
+            }
+            if to > split {
+                out.push_str(&after.paint(&s[from.max(split)..to]));
+            }
+        };
+
+        // neighbouring matches in the same style are painted at once
+        let mut matches: Vec<(usize, usize, Style)> = vec![];
+        for &(start, end, term) in ranges {
+            let style = self.term(term);
+            match matches.last_mut() {
+                Some(last) if last.1 == start && last.2 == style => last.1 = end,
+                _ => matches.push((start, end, style)),
+            }
+        }
+
+        let mut last = 0;
+        for (start, end, style) in matches {
+            plain(last, start, &mut out);
+            out.push_str(&style.paint(&s[start..end]));
+            last = end;
+        }
+        plain(last, s.len(), &mut out);
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn styles_parse() {
+        let style: Style = "Bold bright blue".parse().unwrap();
+        assert_eq!(style.color, Some(Color::BrightBlue));
+        assert!(style.bold && !style.italic);
+
+        let style: Style = "underline #ff8800".parse().unwrap();
+        assert_eq!(
+            style.color,
+            Some(Color::TrueColor {
+                r: 0xff,
+                g: 0x88,
+                b: 0
+            })
+        );
+
+        assert_eq!("".parse::<Style>().unwrap(), Style::default());
+        assert!("bold mauve".parse::<Style>().is_err());
+        assert!("#ff88".parse::<Style>().is_err());
+    }
+
+    #[test]
+    fn terms_get_their_own_color() {
+        colored::control::set_override(true);
+        let palette = Palette::new(&Theme {
+            directory: "blue".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert_eq!(
+            palette.paint_path("./docs/report.md", &[(2, 6, 0), (7, 10, 1)]),
+            "\u{1b}[34m./\u{1b}[0m\u{1b}[1;33mdocs\u{1b}[0m\u{1b}[34m/\u{1b}[0m\u{1b}[1;36mrep\u{1b}[0mort.md"
+        );
+    }
+}
@@ -8,10 +8,17 @@ use ascii_tree::{
     Tree::{self, Leaf, Node},
 };
 
+use super::archives::SEPARATOR;
 use crate::cli::error::BFFError;
 
-pub fn path_to_tree(path: &str) -> Result<String, BFFError> {
-    let path = Path::new(path);
+/// Draws `path` as a tree, `archive` is the archive it is a member of, if any.
+pub fn path_to_tree(path: &str, archive: Option<&str>) -> Result<String, BFFError> {
+    // members of an archive are drawn as if the archive was a directory
+    let path = match archive {
+        Some(a) => format!("{a}/{}", &path[a.len() + SEPARATOR.len()..]),
+        None => path.to_string(),
+    };
+    let path = Path::new(&path);
     let mut res: Vec<&str> = vec![];
     for c in path.components() {
         match c {
@@ -57,7 +64,7 @@ mod tests {
     #[test]
     fn test_single_segment() {
         let path = "foo";
-        let result = path_to_tree(path).unwrap();
+        let result = path_to_tree(path, None).unwrap();
         println!("{result}");
         let expected = "";
         assert_eq!(result, expected);
@@ -66,7 +73,7 @@ mod tests {
     #[test]
     fn test_multi_segment_path() {
         let path = "./bar/baz";
-        let result = path_to_tree(path).unwrap();
+        let result = path_to_tree(path, None).unwrap();
         let expected = r"└─ bar
    └─ baz
 ";
@@ -74,10 +81,23 @@ mod tests {
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn test_archive_member() {
+        let path = "./a!/b.zip!/c!/d.txt";
+        let result = path_to_tree(path, Some("./a!/b.zip")).unwrap();
+        let expected = r"└─ a!
+   └─ b.zip
+      └─ c!
+         └─ d.txt
+";
+
+        assert_eq!(result, expected);
+    }
+
     #[test]
     fn test_dot_path() {
         let path = ".";
-        let result = path_to_tree(path).unwrap();
+        let result = path_to_tree(path, None).unwrap();
         let expected = "";
         assert_eq!(result, expected);
     }
@@ -86,7 +106,7 @@ mod tests {
     #[should_panic(expected = "this shouldn't happen! leave an issue if it does")]
     fn test_empty_path() {
         let path = "";
-        let result = path_to_tree(path).unwrap();
+        let result = path_to_tree(path, None).unwrap();
         let expected = "";
         assert_eq!(result, expected);
     }
@@ -0,0 +1,132 @@
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+    sync::mpsc::{self, Receiver},
+    time::Duration,
+};
+
+use jwalk::WalkDir;
+use log::{info, warn};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::behavior::cache::FileMeta;
+use crate::cli::error::BFFError;
+
+/// Events arriving this close to each other are handled together.
+const BATCH_DELAY: Duration = Duration::from_millis(100);
+
+pub type Events = Receiver<notify::Result<Event>>;
+
+/// A file that appeared or changed, or a file or directory that is gone.
+#[derive(Debug)]
+pub enum Change {
+    Added(String, FileMeta),
+    Removed(String),
+}
+
+/// Starts watching `root` recursively. Events stop when the watcher is dropped.
+pub fn watch(root: &Path) -> Result<(RecommendedWatcher, Events), BFFError> {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    watcher.watch(root, RecursiveMode::Recursive)?;
+
+    info!("watching {}", root.display());
+    Ok((watcher, rx))
+}
+
+/// Waits for the next burst of events and returns the paths they touched, `None` once
+/// the watcher is gone.
+pub fn next_batch(rx: &Events) -> Option<Vec<PathBuf>> {
+    let paths = |event: notify::Result<Event>| match event {
+        Ok(e) if !matches!(e.kind, EventKind::Access(_)) => e.paths,
+        Ok(_) => vec![],
+        Err(e) => {
+            warn!("error watching files: {e}");
+            vec![]
+        }
+    };
+
+    loop {
+        let mut changed = paths(rx.recv().ok()?);
+        while let Ok(event) = rx.recv_timeout(BATCH_DELAY) {
+            changed.extend(paths(event));
+        }
+
+        if !changed.is_empty() {
+            changed.sort();
+            changed.dedup();
+            return Some(changed);
+        }
+    }
+}
+
+/// The path as it appears in the file tree (`./dir/file`), `None` for the root itself and
+/// for hidden paths unless `show_hidden` is set.
+pub fn tree_path(abs: &Path, root: &Path, show_hidden: bool) -> Option<String> {
+    let rel = abs.strip_prefix(root).ok()?;
+    let hidden = rel.components().any(|c| match c {
+        Component::Normal(name) => name.as_encoded_bytes().starts_with(b"."),
+        _ => false,
+    });
+
+    if rel.as_os_str().is_empty() || (hidden && !show_hidden) {
+        return None;
+    }
+
+    Some(Path::new(".").join(rel).display().to_string())
+}
+
+/// Turns changed paths into changed files, a new directory adds every file inside it.
+pub fn changes(changed: &[PathBuf], root: &Path, show_hidden: bool) -> Vec<Change> {
+    let mut out = vec![];
+
+    for abs in changed {
+        let Some(path) = tree_path(abs, root, show_hidden) else {
+            continue;
+        };
+
+        match fs::metadata(abs) {
+            Ok(md) if md.is_file() => out.push(Change::Added(path, FileMeta::from_metadata(&md))),
+            Ok(md) if md.is_dir() => {
+                for entry in WalkDir::new(abs)
+                    .skip_hidden(!show_hidden)
+                    .into_iter()
+                    .flatten()
+                    .filter(|e| e.file_type().is_file())
+                {
+                    if let (Some(path), Ok(md)) = (
+                        tree_path(&entry.path(), root, show_hidden),
+                        entry.metadata(),
+                    ) {
+                        out.push(Change::Added(path, FileMeta::from_metadata(&md)));
+                    }
+                }
+            }
+            Ok(_) => {}
+            Err(_) => out.push(Change::Removed(path)),
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hidden_paths_are_skipped() {
+        let root = Path::new("/home/me/project");
+        assert_eq!(
+            tree_path(&root.join("target/app"), root, false),
+            Some("./target/app".to_string())
+        );
+        assert_eq!(tree_path(&root.join(".git/HEAD"), root, false), None);
+        assert_eq!(
+            tree_path(&root.join(".git/HEAD"), root, true),
+            Some("./.git/HEAD".to_string())
+        );
+        assert_eq!(tree_path(root, root, true), None);
+        assert_eq!(tree_path(Path::new("/elsewhere"), root, true), None);
+    }
+}
@@ -1,8 +1,11 @@
 use clap::{
     builder::{styling::AnsiColor, Styles},
-    Parser, Subcommand,
+    parser::ValueSource,
+    ArgMatches, Parser, Subcommand, ValueEnum,
 };
 
+use crate::options::{CaseMode, OutputFormat, SearchDefaults, SortBy, When};
+
 #[derive(Debug, Parser)]
 #[command(version, about, long_about = None, styles=STYLES)]
 pub struct BFFArgs {
@@ -19,39 +22,402 @@ pub struct BFFArgs {
 pub enum BFFCommands {
     /// Search for files using the CLI
     Search(SearchArgs),
+    /// Search for files interactively, and print the picked ones
+    Pick(SearchArgs),
+    /// Search for files and open the best match, in $VISUAL or $EDITOR for text files
+    Open(SearchArgs),
+    /// Build or refresh the file tree and a trigram index speeding up searches in it
+    Index(IndexArgs),
+    /// Keep the file tree of this directory in memory and up to date, for searches to use
+    #[cfg(unix)]
+    Daemon(DaemonArgs),
+    /// Print the shell integration script, add `eval "$(bff init bash)"` to your shell config
+    Init(InitArgs),
+    /// Print the script registering completions, e.g. `source <(bff completions bash)`
+    Completions(CompletionsArgs),
+    /// Print the settings from every config file that applies here, merged, or edit them
+    Config(ConfigArgs),
+    /// Add, remove or list aliases
+    Alias(AliasArgs),
     /// Delete hidden files used by bff
     Clean,
 }
 
 #[derive(Debug, Parser)]
 pub struct SearchArgs {
-    /// Keywords to search for
-    #[clap()]
+    /// Keywords to search for, wrap a keyword in slashes to use it as a regex (/pattern/)
     pub terms: Vec<String>,
 
+    /// Add a regex search term, can be used multiple times
+    #[clap(short, long, value_name = "PATTERN")]
+    pub regex: Vec<String>,
+
+    /// Add a glob search term (e.g. '*.tar.gz'), can be used multiple times
+    #[clap(short, long, value_name = "PATTERN")]
+    pub glob: Vec<String>,
+
+    /// Add a term matched against the contents of files, can be used multiple times
+    #[clap(long, value_name = "TERM")]
+    pub content: Vec<String>,
+
     /// Only yield results that match all search terms
-    #[clap(short, long)]
+    #[clap(short, long, overrides_with = "no_strict")]
     pub strict: bool,
 
+    /// Undo --strict, when the config turns it on
+    #[clap(long, overrides_with = "strict")]
+    pub no_strict: bool,
+
     /// The number of results to return (default 1)
     #[clap(short, long)]
     pub count: Option<u32>,
 
     /// Display a tree view of the resulting path
-    #[clap(short, long)]
+    #[clap(short, long, overrides_with = "no_tree")]
     pub tree: bool,
 
+    /// Undo --tree, when the config turns it on
+    #[clap(long, overrides_with = "tree")]
+    pub no_tree: bool,
+
     /// Display all possible results (overridden by --count)
-    #[clap(short, long)]
+    #[clap(short, long, overrides_with = "no_all")]
     pub all: bool,
 
+    /// Undo --all, when the config turns it on
+    #[clap(long, overrides_with = "all")]
+    pub no_all: bool,
+
     /// Disable skipping hidden directories (can decrease indexing time drastically)
-    #[clap(short = 'S', long)]
+    #[clap(short = 'S', long, overrides_with = "no_show_hidden")]
     pub show_hidden: bool,
 
+    /// Undo --show-hidden, when the config turns it on
+    #[clap(long, overrides_with = "show_hidden")]
+    pub no_show_hidden: bool,
+
     /// Skip writing the cache file, good for not cluttering directories
-    #[clap(short = 'C', long)]
+    #[clap(short = 'C', long, overrides_with = "cache")]
     pub no_cache: bool,
+
+    /// Undo --no-cache, when the config turns it on
+    #[clap(long, overrides_with = "no_cache")]
+    pub cache: bool,
+
+    /// Also search the paths of files inside zip, tar, tar.gz and tar.zst archives
+    #[clap(long, overrides_with = "no_archives")]
+    pub archives: bool,
+
+    /// Undo --archives, when the config turns it on
+    #[clap(long, overrides_with = "archives")]
+    pub no_archives: bool,
+
+    /// Rank results higher when terms match in order, in consecutive path components
+    #[clap(short, long, overrides_with = "no_ordered")]
+    pub ordered: bool,
+
+    /// Undo --ordered, when the config turns it on
+    #[clap(long, overrides_with = "ordered")]
+    pub no_ordered: bool,
+
+    /// Match word prefixes and initialisms (mm -> meetingMinutes), ranking them above mid-word hits
+    #[clap(short, long, overrides_with = "no_words")]
+    pub words: bool,
+
+    /// Undo --words, when the config turns it on
+    #[clap(long, overrides_with = "words")]
+    pub no_words: bool,
+
+    /// How to treat upper and lower case, smart ignores case unless a term has an uppercase letter
+    #[clap(long, value_enum, default_value_t = CaseMode::Sensitive)]
+    pub case: CaseMode,
+
+    /// Ignore accents and other diacritics (resume matches résumé)
+    #[clap(short, long, overrides_with = "no_fold")]
+    pub fold: bool,
+
+    /// Undo --fold, when the config turns it on
+    #[clap(long, overrides_with = "fold")]
+    pub no_fold: bool,
+
+    /// Let date terms (may, 2024-07, yesterday) also match the modification date of files
+    #[clap(short, long, overrides_with = "no_mtime")]
+    pub mtime: bool,
+
+    /// Undo --mtime, when the config turns it on
+    #[clap(long, overrides_with = "mtime")]
+    pub no_mtime: bool,
+
+    /// Only yield files modified within a duration (3d, 12h) or since a date (2024-01-01)
+    #[clap(long, value_name = "WHEN")]
+    pub newer: Option<String>,
+
+    /// Only yield files modified longer ago than a duration (3d, 12h) or before a date (2024-01-01)
+    #[clap(long, value_name = "WHEN")]
+    pub older: Option<String>,
+
+    /// Only yield files of a size, +10M for at least, -1k for at most, can be used multiple times
+    #[clap(long, value_name = "SIZE", allow_hyphen_values = true)]
+    pub size: Vec<String>,
+
+    /// Only yield executable files
+    #[clap(long)]
+    pub executable: bool,
+
+    /// Order of results with the same score
+    #[clap(long, value_enum, default_value_t = SortBy::Score)]
+    pub sort: SortBy,
+
+    /// Open an interactive picker searching as you type, and print the picked files
+    #[clap(long)]
+    pub interactive: bool,
+
+    /// How results are printed
+    #[clap(long, value_enum, default_value_t = OutputFormat::Plain)]
+    pub format: OutputFormat,
+
+    /// When to highlight matches, `auto` does when printing to a terminal and NO_COLOR is unset
+    #[clap(long, value_enum, default_value_t = When::Auto)]
+    pub color: When,
+
+    /// When to make result paths clickable links, `auto` does in terminals known to support them
+    #[clap(long, value_enum, default_value_t = When::Auto)]
+    pub hyperlink: When,
+
+    /// Print paths separated by NUL characters, for `xargs -0` (same as --format null)
+    #[clap(short = '0', long, conflicts_with = "format")]
+    pub print0: bool,
+
+    /// Keep running after printing the results, and print new matching files as they appear
+    #[clap(long)]
+    pub watch: bool,
+
+    /// With --watch, also print printed files that get removed, prefixed with '- '
+    #[clap(long, requires = "watch")]
+    pub removed: bool,
+
+    /// Run a command for each result instead of printing it, ends at ';' or the end of the line.
+    /// {} is the path, {/} its basename, {//} its directory, {.} and {/.} drop the extension
+    #[clap(
+        short = 'x',
+        long,
+        num_args = 1..,
+        allow_hyphen_values = true,
+        value_terminator = ";",
+        value_name = "CMD",
+        conflicts_with_all = ["exec_batch", "interactive", "watch", "format", "print0"]
+    )]
+    pub exec: Vec<String>,
+
+    /// Run a command once with all results instead of printing them, placeholders as in --exec
+    #[clap(
+        short = 'X',
+        long,
+        num_args = 1..,
+        allow_hyphen_values = true,
+        value_terminator = ";",
+        value_name = "CMD",
+        conflicts_with_all = ["interactive", "watch", "format", "print0"]
+    )]
+    pub exec_batch: Vec<String>,
+
+    /// How many --exec commands run at once (default: the number of CPUs), use 1 for
+    /// commands that read from the terminal
+    #[clap(short = 'j', long, value_name = "N")]
+    pub threads: Option<usize>,
+
+    /// Print the directory of each result instead of the result itself
+    #[clap(long, conflicts_with = "watch")]
+    pub dir_of: bool,
+}
+
+impl SearchArgs {
+    /// Takes the flags missing from the command line from the `[search]` config, `matches`
+    /// are those of the subcommand.
+    pub fn apply_defaults(&mut self, defaults: &SearchDefaults, matches: &ArgMatches) {
+        let given = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+        let flag = |value: &mut bool, id: &str, negation: &str, default: Option<bool>| {
+            if !given(id)
+                && !given(negation)
+                && let Some(default) = default
+            {
+                *value = default;
+            }
+        };
+
+        flag(&mut self.strict, "strict", "no_strict", defaults.strict);
+        flag(&mut self.all, "all", "no_all", defaults.all);
+        flag(&mut self.tree, "tree", "no_tree", defaults.tree);
+        flag(
+            &mut self.show_hidden,
+            "show_hidden",
+            "no_show_hidden",
+            defaults.show_hidden,
+        );
+        flag(&mut self.no_cache, "no_cache", "cache", defaults.no_cache);
+        flag(
+            &mut self.archives,
+            "archives",
+            "no_archives",
+            defaults.archives,
+        );
+        flag(&mut self.ordered, "ordered", "no_ordered", defaults.ordered);
+        flag(&mut self.words, "words", "no_words", defaults.words);
+        flag(&mut self.fold, "fold", "no_fold", defaults.fold);
+        flag(&mut self.mtime, "mtime", "no_mtime", defaults.mtime);
+
+        // --count beats --all, so a configured count must not beat --all given here
+        if !given("count") && !given("all") {
+            self.count = self.count.or(defaults.count);
+        }
+        if !given("case")
+            && let Some(case) = defaults.case
+        {
+            self.case = case;
+        }
+        if !given("sort")
+            && let Some(sort) = defaults.sort
+        {
+            self.sort = sort;
+        }
+        // --print0 stands for --format null
+        if !given("format")
+            && !given("print0")
+            && let Some(format) = defaults.format
+        {
+            self.format = format;
+        }
+        if !given("color")
+            && let Some(color) = defaults.color
+        {
+            self.color = color;
+        }
+        if !given("hyperlink")
+            && let Some(hyperlink) = defaults.hyperlink
+        {
+            self.hyperlink = hyperlink;
+        }
+    }
+}
+
+#[derive(Debug, Parser)]
+pub struct IndexArgs {
+    /// Disable skipping hidden directories, searches need the same flag to use the index
+    #[clap(short = 'S', long)]
+    pub show_hidden: bool,
+
+    /// Also index the files inside archives, searches need the same flag to use the index
+    #[clap(long)]
+    pub archives: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct DaemonArgs {
+    /// Disable skipping hidden directories, searches need the same flag to use the daemon
+    #[clap(short = 'S', long)]
+    pub show_hidden: bool,
+
+    /// Also keep the files inside archives, searches need the same flag to use the daemon
+    #[clap(long)]
+    pub archives: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct InitArgs {
+    #[clap(value_enum)]
+    pub shell: Shell,
+}
+
+#[derive(Debug, Parser)]
+pub struct CompletionsArgs {
+    #[clap(value_enum)]
+    pub shell: Shell,
+}
+
+#[derive(Debug, Parser)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct ConfigArgs {
+    #[clap(subcommand)]
+    pub cmd: Option<ConfigCommands>,
+
+    /// Print the file each setting comes from
+    #[clap(long)]
+    pub show_origin: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommands {
+    /// Create a config file with every setting commented out
+    Init(ConfigTarget),
+    /// Print the merged settings, the default
+    Show {
+        /// Print the file each setting comes from
+        #[clap(long)]
+        show_origin: bool,
+    },
+    /// Print the value of a setting, such as `search.count`, after merging
+    Get { key: String },
+    /// Set a setting, the value is read as TOML (`true`, `20`, `["red"]`) or else as a string
+    Set {
+        key: String,
+        value: String,
+        #[clap(flatten)]
+        target: ConfigTarget,
+    },
+    /// Remove a setting
+    Unset {
+        key: String,
+        #[clap(flatten)]
+        target: ConfigTarget,
+    },
+    /// Open the config file in $VISUAL or $EDITOR, creating it if needed
+    Edit(ConfigTarget),
+}
+
+/// The config file to edit.
+#[derive(Debug, Parser)]
+pub struct ConfigTarget {
+    /// Edit the global config file instead of the one in this directory
+    #[clap(short, long)]
+    pub global: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct AliasArgs {
+    #[clap(subcommand)]
+    pub cmd: AliasCommands,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AliasCommands {
+    /// Add an alias, or replace it
+    Add {
+        name: String,
+        /// The terms the alias expands into
+        #[clap(required = true)]
+        expansion: Vec<String>,
+        #[clap(flatten)]
+        target: ConfigTarget,
+    },
+    /// Remove an alias
+    Remove {
+        name: String,
+        #[clap(flatten)]
+        target: ConfigTarget,
+    },
+    /// List the aliases that apply here
+    List {
+        /// Print the file each alias comes from
+        #[clap(long)]
+        show_origin: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
 }
 
 const STYLES: Styles = Styles::styled()
@@ -59,3 +425,55 @@ const STYLES: Styles = Styles::styled()
     .usage(AnsiColor::Yellow.on_default())
     .literal(AnsiColor::BrightCyan.on_default())
     .placeholder(AnsiColor::BrightWhite.on_default());
+
+#[cfg(test)]
+mod tests {
+    use clap::{CommandFactory, FromArgMatches};
+
+    use super::*;
+
+    fn search(line: &[&str], defaults: &str) -> SearchArgs {
+        let matches = BFFArgs::command().get_matches_from(line);
+        let (_, sub) = matches.subcommand().unwrap();
+        let mut obj = SearchArgs::from_arg_matches(sub).unwrap();
+        obj.apply_defaults(&toml::from_str(defaults).unwrap(), sub);
+        obj
+    }
+
+    #[test]
+    fn flags_override_search_defaults() {
+        let defaults = "strict = true\ncount = 20\ntree = true\ncase = \"sensitive\"\n";
+
+        let obj = search(&["bff", "search", "notes"], defaults);
+        assert!(obj.strict && obj.tree);
+        assert_eq!(obj.count, Some(20));
+        assert!(matches!(obj.case, CaseMode::Sensitive));
+
+        let obj = search(
+            &[
+                "bff",
+                "search",
+                "--no-strict",
+                "-c",
+                "5",
+                "--case",
+                "smart",
+                "notes",
+            ],
+            defaults,
+        );
+        assert!(!obj.strict && obj.tree);
+        assert_eq!(obj.count, Some(5));
+        assert!(matches!(obj.case, CaseMode::Smart));
+
+        let obj = search(
+            &["bff", "search", "--no-strict", "--strict", "notes"],
+            defaults,
+        );
+        assert!(obj.strict);
+
+        let obj = search(&["bff", "search", "-a", "notes"], defaults);
+        assert!(obj.all);
+        assert_eq!(obj.count, None);
+    }
+}
@@ -1,6 +1,5 @@
 use std::{fmt, io};
 
-use log::error;
 use rmp_serde::{decode, encode};
 use thiserror::Error;
 use toml::de;
@@ -19,6 +18,15 @@ pub enum BFFError {
     #[error("Error deserializing from TOML: {0}")]
     TOMLDeError(#[from] de::Error),
 
+    #[error("Error reading config {0}: {1}")]
+    ConfigError(String, de::Error),
+
+    #[error("Unable to edit config: {0}")]
+    ConfigEdit(String),
+
+    #[error("{0} is not set")]
+    NotSet(String),
+
     #[error("Error serializing to msgpack: {0}")]
     RMPEncodeError(#[from] encode::Error),
 
@@ -36,4 +44,37 @@ pub enum BFFError {
 
     #[error("Failure formatting: {0}")]
     FormatError(#[from] fmt::Error),
+
+    #[error("Invalid regex term: {0}")]
+    RegexError(#[from] regex::Error),
+
+    #[error("Invalid glob term: {0}")]
+    GlobError(#[from] globset::Error),
+
+    #[error("Invalid filter: {0}")]
+    InvalidFilter(String),
+
+    #[error("Invalid style in theme: {0}")]
+    InvalidStyle(String),
+
+    #[error("Error reading zip archive: {0}")]
+    ZipError(#[from] zip::result::ZipError),
+
+    #[error("Error watching files: {0}")]
+    NotifyError(#[from] notify::Error),
+
+    #[error("Daemon error: {0}")]
+    DaemonError(String),
+
+    #[error("Nothing was picked")]
+    Aborted,
+
+    #[error("Error serializing to JSON: {0}")]
+    JSONError(#[from] serde_json::Error),
+
+    #[error("Unable to run command: {0}")]
+    ExecError(String),
+
+    #[error("Command exited with status {0}")]
+    CommandFailed(i32),
 }
@@ -1,15 +1,250 @@
-use std::fs;
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use log::{info, warn};
+use toml::{Table, Value};
 
 use crate::cli::error::BFFError;
 use crate::config::schema;
 
+/// A config file and its settings, as written.
+#[derive(Debug)]
+pub struct Layer {
+    pub path: PathBuf,
+    /// Whether the file marks the top of the hierarchy.
+    pub root: bool,
+    pub table: Table,
+}
+
+/// The config merged from every file that applies to the working directory.
+#[derive(Debug, Default)]
+pub struct MergedConfig {
+    pub table: Table,
+    /// The file each setting comes from, by dotted key (`alias.stubs`).
+    pub origins: BTreeMap<String, PathBuf>,
+}
+
+/// The global config file, in `$XDG_CONFIG_HOME/bff/` or the platform's config directory.
+pub fn global_config_path() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+        .or_else(dirs::config_dir)
+        .map(|d| {
+            d.join(env!("CARGO_PKG_NAME"))
+                .join(schema::GLOBAL_CONFIG_FILE)
+        })
+}
+
+/// Whether a config file can be trusted, since `bff open` runs its `[open]` commands: it
+/// must be owned by the current user, and not writable by other users. Files writable by
+/// the user's own group are fine, as umasks commonly allow that.
+#[cfg(unix)]
+fn is_trusted(md: &fs::Metadata) -> bool {
+    use nix::unistd::{getegid, geteuid};
+    use std::os::unix::fs::MetadataExt;
+
+    let others_write = md.mode() & 0o002 != 0;
+    let group_write = md.mode() & 0o020 != 0 && md.gid() != getegid().as_raw();
+    md.uid() == geteuid().as_raw() && !others_write && !group_write
+}
+
+#[cfg(not(unix))]
+fn is_trusted(_md: &fs::Metadata) -> bool {
+    true
+}
+
+fn read_layer(path: &Path) -> Result<Option<Layer>, BFFError> {
+    if !fs::exists(path)? {
+        return Ok(None);
+    }
+    if !is_trusted(&fs::metadata(path)?) {
+        warn!(
+            "ignoring config {}: it isn't yours, or others can write to it",
+            path.display()
+        );
+        return Ok(None);
+    }
+
+    info!("reading config: {}", path.display());
+    let error = |e| BFFError::ConfigError(path.display().to_string(), e);
+    let table: Table = fs::read_to_string(path)?.parse().map_err(error)?;
+    // checked on its own, so mistakes are reported with the file they are in. Unknown
+    // settings only warn, one stray key shouldn't stop every search
+    let conf = schema::from_table(table.clone(), |key| {
+        warn!("ignoring unknown setting {key} in {}", path.display())
+    })
+    .map_err(error)?;
+
+    Ok(Some(Layer {
+        path: path.to_path_buf(),
+        root: conf.root == Some(true),
+        table,
+    }))
+}
+
+/// The config files applying to `dir`, the farthest first: the global config, then the
+/// `.bff.toml` of every ancestor up to the filesystem root or one with `root = true`, then
+/// the one in `dir`.
+pub fn find_layers(dir: &Path, global: Option<&Path>) -> Result<Vec<Layer>, BFFError> {
+    let mut layers = vec![];
+    for dir in dir.ancestors() {
+        if let Some(layer) = read_layer(&dir.join(schema::CONFIG_FILE))? {
+            let root = layer.root;
+            layers.push(layer);
+            if root {
+                break;
+            }
+        }
+    }
+
+    if let Some(global) = global
+        && let Some(layer) = read_layer(global)?
+    {
+        layers.push(layer);
+    }
+
+    layers.reverse();
+    Ok(layers)
+}
+
+/// Merges the layers, settings of later layers replace those of earlier ones. Tables are
+/// merged key by key, anything else is replaced as a whole.
+pub fn merge(layers: &[Layer]) -> MergedConfig {
+    let mut merged = MergedConfig::default();
+    for layer in layers {
+        merge_table(
+            &mut merged.table,
+            &layer.table,
+            "",
+            &layer.path,
+            &mut merged.origins,
+        );
+    }
+    merged
+}
+
+fn merge_table(
+    into: &mut Table,
+    from: &Table,
+    prefix: &str,
+    path: &Path,
+    origins: &mut BTreeMap<String, PathBuf>,
+) {
+    for (key, value) in from {
+        let dotted = format!("{prefix}{key}");
+
+        if let (Some(Value::Table(old)), Value::Table(new)) = (into.get_mut(key), value) {
+            merge_table(old, new, &format!("{dotted}."), path, origins);
+            continue;
+        }
+
+        let nested = format!("{dotted}.");
+        origins.retain(|k, _| *k != dotted && !k.starts_with(&nested));
+        record_origins(value, &dotted, path, origins);
+        into.insert(key.clone(), value.clone());
+    }
+}
+
+fn record_origins(
+    value: &Value,
+    dotted: &str,
+    path: &Path,
+    origins: &mut BTreeMap<String, PathBuf>,
+) {
+    match value {
+        Value::Table(table) => {
+            for (key, value) in table {
+                record_origins(value, &format!("{dotted}.{key}"), path, origins);
+            }
+        }
+        _ => {
+            origins.insert(dotted.to_string(), path.to_path_buf());
+        }
+    }
+}
+
+/// Loads and merges the configs applying to the working directory.
+pub fn load_config() -> Result<MergedConfig, BFFError> {
+    let layers = find_layers(&env::current_dir()?, global_config_path().as_deref())?;
+    Ok(merge(&layers))
+}
+
 pub fn read_config() -> Result<schema::TreeConfig, BFFError> {
-    if !fs::exists(schema::CONFIG_FILE)? {
+    let merged = load_config()?;
+    if merged.origins.is_empty() {
         return Err(BFFError::NoConfig);
     }
 
-    let toml_str = fs::read_to_string(schema::CONFIG_FILE)?;
-    let config: schema::TreeConfig = toml::from_str(&toml_str)?;
+    Ok(merged.table.try_into()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn closer_configs_win() {
+        let tmp = tempfile::tempdir().unwrap();
+        let base = tmp.path();
+        let project = base.join("project");
+        let local = project.join("src");
+        fs::create_dir_all(&local).unwrap();
+
+        let global = base.join("global.toml");
+        fs::write(
+            &global,
+            "[alias]\nteam = \"from global\"\nmine = \"global\"\n",
+        )
+        .unwrap();
+        fs::write(base.join(".bff.toml"), "[alias]\nabove = \"above root\"\n").unwrap();
+        fs::write(
+            project.join(".bff.toml"),
+            "root = true\n[alias]\nteam = \"from project\"\n[theme]\nmatches = [\"red\"]\n",
+        )
+        .unwrap();
+        // settings bff doesn't know only warn
+        fs::write(
+            local.join(".bff.toml"),
+            "[theme]\ndirectory = \"blue\"\ncolour = \"old\"\n",
+        )
+        .unwrap();
+
+        let layers = find_layers(&local, Some(&global)).unwrap();
+        let merged = merge(&layers);
+
+        let conf: schema::TreeConfig = merged.table.clone().try_into().unwrap();
+        assert_eq!(conf.alias["team"], "from project");
+        assert_eq!(conf.alias["mine"], "global");
+        assert!(!conf.alias.contains_key("above"));
+        assert_eq!(conf.theme.matches, ["red"]);
+        assert_eq!(conf.theme.directory, "blue");
 
-    Ok(config)
+        assert_eq!(merged.origins["alias.team"], project.join(".bff.toml"));
+        assert_eq!(merged.origins["alias.mine"], global);
+        assert_eq!(merged.origins["theme.directory"], local.join(".bff.toml"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn configs_others_can_write_are_ignored() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let base = tmp.path();
+        let project = base.join("project");
+        fs::create_dir_all(&project).unwrap();
+
+        let shared = base.join(".bff.toml");
+        fs::write(&shared, "[open]\npdf = \"evil\"\n").unwrap();
+        fs::set_permissions(&shared, fs::Permissions::from_mode(0o666)).unwrap();
+        fs::write(project.join(".bff.toml"), "[alias]\nmine = \"mine\"\n").unwrap();
+
+        let layers = find_layers(&project, None).unwrap();
+        assert_eq!(layers.len(), 1);
+        assert_eq!(layers[0].path, project.join(".bff.toml"));
+    }
 }
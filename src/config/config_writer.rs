@@ -0,0 +1,251 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use log::info;
+use serde::de::Error as _;
+use toml::de;
+use toml_edit::{DocumentMut, Item, Key, Table, TableLike, Value};
+
+use crate::cli::error::BFFError::{self, ConfigEdit};
+use crate::config::config_reader::global_config_path;
+use crate::config::schema::{self, TreeConfig};
+
+/// Written by `bff config init`.
+const TEMPLATE: &str = include_str!("template.toml");
+
+/// The config file edited by `bff config` and `bff alias`: the global one, or the one in
+/// the working directory.
+pub fn config_path(global: bool) -> Result<PathBuf, BFFError> {
+    if global {
+        global_config_path().ok_or_else(|| ConfigEdit("no config directory found".into()))
+    } else {
+        Ok(env::current_dir()?.join(schema::CONFIG_FILE))
+    }
+}
+
+/// A dotted key such as `search.strict`, parts may be quoted as in `open.".tar.gz"`.
+pub fn parse_key(key: &str) -> Result<Vec<Key>, BFFError> {
+    Key::parse(key).map_err(|e| ConfigEdit(format!("invalid key {key}: {e}")))
+}
+
+/// A value as written in TOML (`true`, `20`, `["red", "blue"]`), anything else is taken as
+/// a string.
+pub fn parse_value(raw: &str) -> Value {
+    raw.parse().unwrap_or_else(|_| raw.into())
+}
+
+/// Creates the config file with every setting commented out.
+pub fn init(path: &Path) -> Result<(), BFFError> {
+    if fs::exists(path)? {
+        return Err(ConfigEdit(format!("{} already exists", path.display())));
+    }
+
+    write(path, TEMPLATE)
+}
+
+fn write(path: &Path, contents: &str) -> Result<(), BFFError> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    info!("writing config: {}", path.display());
+    fs::write(path, contents)?;
+    Ok(())
+}
+
+/// Checks that `contents`, written to `path` in place of `before`, is valid TOML with
+/// settings of the right types, and returns the config it holds.
+///
+/// Stricter than reading a config, which only warns about unknown settings: a typo made
+/// while editing is better caught right away. Unknown settings already in `before` are
+/// let through, so they can still be unset.
+pub fn validate(path: &Path, contents: &str, before: &str) -> Result<TreeConfig, BFFError> {
+    let error = |e| BFFError::ConfigError(path.display().to_string(), e);
+    let mut unknown = vec![];
+    let conf = schema::from_table(toml::from_str(contents).map_err(error)?, |key| {
+        unknown.push(key)
+    })
+    .map_err(error)?;
+
+    let mut known = vec![];
+    if let Ok(table) = toml::from_str(before) {
+        let _ = schema::from_table(table, |key| known.push(key));
+    }
+
+    match unknown.into_iter().find(|key| !known.contains(key)) {
+        Some(key) => Err(error(de::Error::custom(format!("unknown setting {key}")))),
+        None => Ok(conf),
+    }
+}
+
+/// A config file being edited, comments and formatting are kept as they are.
+#[derive(Debug, Clone)]
+pub struct ConfigFile {
+    pub path: PathBuf,
+    doc: DocumentMut,
+    /// The contents of the file when it was opened.
+    before: String,
+}
+
+impl ConfigFile {
+    /// Reads the file at `path`, which doesn't need to exist.
+    pub fn open(path: PathBuf) -> Result<Self, BFFError> {
+        let before = match fs::exists(&path)? {
+            true => fs::read_to_string(&path)?,
+            false => String::new(),
+        };
+        let doc = before
+            .parse()
+            .map_err(|e| ConfigEdit(format!("{}: {e}", path.display())))?;
+
+        Ok(ConfigFile { path, doc, before })
+    }
+
+    /// The table holding keys under `parents`, the tables on the way are created when
+    /// `create` is set.
+    fn table(
+        &mut self,
+        parents: &[Key],
+        create: bool,
+    ) -> Result<Option<&mut dyn TableLike>, BFFError> {
+        let mut table: &mut dyn TableLike = self.doc.as_table_mut();
+        for key in parents {
+            if !table.contains_key(key.get()) {
+                if !create {
+                    return Ok(None);
+                }
+                let mut new = Table::new();
+                new.set_implicit(true);
+                table.insert(key.get(), Item::Table(new));
+            }
+
+            table = table
+                .get_mut(key.get())
+                .and_then(Item::as_table_like_mut)
+                .ok_or_else(|| ConfigEdit(format!("{} is not a table", key.get())))?;
+        }
+        Ok(Some(table))
+    }
+
+    /// Sets the value at `path`, keeping the comments around a value it replaces.
+    pub fn set(&mut self, path: &[Key], mut value: Value) -> Result<(), BFFError> {
+        let (last, parents) = split(path)?;
+        let Some(table) = self.table(parents, true)? else {
+            unreachable!("missing tables are created")
+        };
+
+        match table.get_mut(last.get()) {
+            Some(Item::Value(old)) => {
+                *value.decor_mut() = old.decor().clone();
+                *old = value;
+            }
+            Some(_) => return Err(ConfigEdit(format!("{} is a table", dotted(path)))),
+            None => {
+                table.insert(last.get(), Item::Value(value));
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes the setting at `path`, fails when it isn't in the file.
+    pub fn unset(&mut self, path: &[Key]) -> Result<(), BFFError> {
+        let (last, parents) = split(path)?;
+        let removed = self
+            .table(parents, false)?
+            .and_then(|table| table.remove(last.get()));
+
+        match removed {
+            Some(_) => Ok(()),
+            None => Err(ConfigEdit(format!(
+                "{} is not set in {}",
+                dotted(path),
+                self.path.display()
+            ))),
+        }
+    }
+
+    /// The config the file holds as edited so far, see [`validate`].
+    pub fn config(&self) -> Result<TreeConfig, BFFError> {
+        validate(&self.path, &self.doc.to_string(), &self.before)
+    }
+
+    /// Validates the file and writes it.
+    pub fn save(&self) -> Result<(), BFFError> {
+        let contents = self.doc.to_string();
+        validate(&self.path, &contents, &self.before)?;
+        write(&self.path, &contents)
+    }
+}
+
+fn split(path: &[Key]) -> Result<(&Key, &[Key]), BFFError> {
+    path.split_last()
+        .ok_or_else(|| ConfigEdit("empty key".into()))
+}
+
+/// Joins the parts of a key back together, quoting them where needed.
+pub fn dotted(path: &[Key]) -> String {
+    path.iter()
+        .map(|k| k.display_repr().into_owned())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(contents: &str) -> ConfigFile {
+        ConfigFile {
+            path: PathBuf::from(".bff.toml"),
+            doc: contents.parse().unwrap(),
+            before: contents.to_string(),
+        }
+    }
+
+    #[test]
+    fn edits_keep_comments() {
+        let mut file = edit("# my aliases\n[alias]\nstubs = \"checkstubs\" # payday\n");
+
+        file.set(
+            &parse_key("alias.stubs").unwrap(),
+            parse_value("payment checkstubs"),
+        )
+        .unwrap();
+        file.set(&parse_key("search.count").unwrap(), parse_value("20"))
+            .unwrap();
+        file.set(&parse_key("open.\"tar.gz\"").unwrap(), parse_value("ark"))
+            .unwrap();
+        assert_eq!(
+            file.doc.to_string(),
+            "# my aliases\n[alias]\nstubs = \"payment checkstubs\" # payday\n\n\
+             [search]\ncount = 20\n\n[open]\n\"tar.gz\" = \"ark\"\n"
+        );
+
+        file.unset(&parse_key("search.count").unwrap()).unwrap();
+        assert!(file.unset(&parse_key("search.count").unwrap()).is_err());
+        assert!(file.unset(&parse_key("theme.tree").unwrap()).is_err());
+        assert!(file
+            .set(&parse_key("alias.stubs.x").unwrap(), parse_value("1"))
+            .is_err());
+
+        assert!(file.config().is_ok());
+        assert!(validate(&file.path, "[search]\ncount = \"many\"\n", "").is_err());
+        assert!(validate(&file.path, TEMPLATE, "").is_ok());
+    }
+
+    #[test]
+    fn only_new_unknown_settings_are_rejected() {
+        let typo = "[search]\ncuont = 20\n";
+        assert!(validate(Path::new(".bff.toml"), typo, "").is_err());
+
+        // a stray setting doesn't stop other edits, nor its own removal
+        let mut file = edit(typo);
+        file.set(&parse_key("search.count").unwrap(), parse_value("20"))
+            .unwrap();
+        assert!(file.config().is_ok());
+        file.unset(&parse_key("search.cuont").unwrap()).unwrap();
+        assert!(file.config().is_ok());
+    }
+}
@@ -1,2 +1,3 @@
 pub mod config_reader;
+pub mod config_writer;
 pub mod schema;
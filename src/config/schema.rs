@@ -1,10 +1,58 @@
 use std::collections::HashMap;
 
 use serde::Deserialize;
+use toml::{de, Table, Value};
+
+use crate::options::SearchDefaults;
 
 pub const CONFIG_FILE: &str = ".bff.toml";
 
-#[derive(Debug, Deserialize, Default)]
+/// Name of the global config file, in the config directory of bff.
+pub const GLOBAL_CONFIG_FILE: &str = "config.toml";
+
+#[derive(Debug, Clone, Deserialize, Default)]
 pub struct TreeConfig {
+    /// Stops the search for config files in parent directories.
+    pub root: Option<bool>,
+    #[serde(default)]
     pub alias: HashMap<String, String>,
+    #[serde(default)]
+    pub theme: Theme,
+    /// Commands opening files by extension for `bff open`, `{}` is the path as in `--exec`.
+    #[serde(default)]
+    pub open: HashMap<String, String>,
+    #[serde(default)]
+    pub search: SearchDefaults,
+}
+
+/// Styles used when printing results, such as `"bold yellow"`, `"italic #ff8800"` or `""`
+/// for none.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    /// One style per query term, cycled when there are more terms than styles.
+    pub matches: Vec<String>,
+    pub directory: String,
+    pub basename: String,
+    pub tree: String,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            matches: ["yellow", "cyan", "magenta", "green", "blue", "red"]
+                .iter()
+                .map(|c| format!("bold {c}"))
+                .collect(),
+            directory: String::new(),
+            basename: String::new(),
+            tree: String::new(),
+        }
+    }
+}
+
+/// Reads the config in `table`, calling `unknown` with the dotted key of every setting
+/// that isn't one, such as a typo or a setting of another version of bff.
+pub fn from_table(table: Table, mut unknown: impl FnMut(String)) -> Result<TreeConfig, de::Error> {
+    serde_ignored::deserialize(Value::Table(table), |key| unknown(key.to_string()))
 }
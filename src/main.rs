@@ -1,15 +1,29 @@
 mod behavior;
 mod cli;
 mod config;
+mod options;
 mod parser;
 
-use behavior::{cache::clean, search::run_search};
-use clap::Parser;
+use std::process;
+
+#[cfg(unix)]
+use behavior::daemon::run_daemon;
+use behavior::{
+    cache::clean,
+    completions::{complete, run_completions},
+    config::{run_alias, run_config},
+    index::run_index,
+    init::run_init,
+    open::run_open,
+    pick::run_pick,
+    search::run_search,
+};
+use clap::{CommandFactory, FromArgMatches};
 use cli::error::BFFError;
 use cli::{
     arg_parser::{
         BFFArgs,
-        BFFCommands::{Clean, Search},
+        BFFCommands::{self, Alias, Clean, Completions, Config, Index, Init, Open, Pick, Search},
     },
     error::BFFError::NoConfig,
 };
@@ -19,7 +33,9 @@ use log::{info, warn, LevelFilter};
 use main_error::MainError;
 
 fn run() -> Result<(), BFFError> {
-    let args = BFFArgs::parse();
+    complete();
+    let matches = BFFArgs::command().get_matches();
+    let args = BFFArgs::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
 
     let level = if args.verbose {
         LevelFilter::Info
@@ -30,6 +46,19 @@ fn run() -> Result<(), BFFError> {
 
     info!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
 
+    // these don't use the config or read and write config files themselves, and must work
+    // when a config is broken
+    let mut cmd = match args.cmd {
+        Init(obj) => {
+            run_init(obj);
+            return Ok(());
+        }
+        Completions(obj) => return run_completions(obj),
+        Config(obj) => return run_config(obj),
+        Alias(obj) => return run_alias(obj),
+        cmd => cmd,
+    };
+
     let conf = match read_config() {
         Ok(c) => c,
         Err(NoConfig) => {
@@ -43,18 +72,40 @@ fn run() -> Result<(), BFFError> {
 
     info!("using config: {conf:?}");
 
-    match args.cmd {
+    if let (Search(obj) | Pick(obj) | Open(obj), Some((_, sub))) = (&mut cmd, matches.subcommand())
+    {
+        obj.apply_defaults(&conf.search, sub);
+    }
+
+    match cmd {
         Search(obj) => {
             run_search(obj, conf)?;
         }
 
+        Pick(obj) => run_pick(obj, conf)?,
+
+        Open(obj) => run_open(obj, conf)?,
+
+        Index(obj) => run_index(obj)?,
+
+        #[cfg(unix)]
+        BFFCommands::Daemon(obj) => run_daemon(obj)?,
+
+        Init(_) | Completions(_) | Config(_) | Alias(_) => {
+            unreachable!("run before reading the config")
+        }
+
         Clean => clean()?,
     }
     Ok(())
 }
 
 fn main() -> Result<(), MainError> {
-    run().map_err(Into::<MainError>::into)?;
+    match run() {
+        // the command run with --exec has already reported what went wrong
+        Err(BFFError::CommandFailed(code)) => process::exit(code),
+        res => res.map_err(Into::<MainError>::into)?,
+    }
     info!("end execution successfully");
     Ok(())
 }
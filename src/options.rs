@@ -0,0 +1,96 @@
+//! Choices shared by the command line, the config file and the search itself, kept apart
+//! so the search code doesn't depend on how the choices are made.
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+/// How to order results with the same score.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortBy {
+    /// Keep the order files were found in
+    #[default]
+    Score,
+    /// Files picked, opened or run a command on through bff most often and most recently first
+    Frecency,
+    /// Most recently modified first
+    Mtime,
+    /// Alphabetically
+    Path,
+    /// Largest first
+    Size,
+    /// Shallowest first
+    Depth,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// Highlighted paths, one per line
+    #[default]
+    Plain,
+    /// A JSON array of results with their score, matched terms and highlight ranges
+    Json,
+    /// One JSON result per line
+    Jsonl,
+    /// Paths only, each followed by a NUL character
+    Null,
+}
+
+/// When an output feature is turned on, for `--color` and `--hyperlink`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum When {
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaseMode {
+    Sensitive,
+    Insensitive,
+    Smart,
+}
+
+/// Defaults for the flags of `bff search`, `pick` and `open`, named after the long flags.
+/// Flags given on the command line win.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct SearchDefaults {
+    pub strict: Option<bool>,
+    pub count: Option<u32>,
+    pub all: Option<bool>,
+    pub tree: Option<bool>,
+    pub show_hidden: Option<bool>,
+    pub no_cache: Option<bool>,
+    pub archives: Option<bool>,
+    pub ordered: Option<bool>,
+    pub words: Option<bool>,
+    pub fold: Option<bool>,
+    pub mtime: Option<bool>,
+    pub case: Option<CaseMode>,
+    pub sort: Option<SortBy>,
+    pub format: Option<OutputFormat>,
+    pub color: Option<When>,
+    pub hyperlink: Option<When>,
+}
+
+/// Options that change how a path is scored against the query.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MatchOptions {
+    /// Reward terms that match in query order, in separate consecutive components.
+    pub ordered: bool,
+    /// Match word prefixes and initialisms, ranking them above mid-word hits.
+    pub words: bool,
+    /// Compare paths and terms case insensitively.
+    pub ignore_case: bool,
+    /// Strip accents and other diacritics before comparing.
+    pub fold: bool,
+    /// Let date terms match the modification date of a file.
+    pub mtime: bool,
+    /// Decides the order of files with the same score.
+    pub sort: SortBy,
+}
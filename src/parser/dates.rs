@@ -0,0 +1,219 @@
+use jiff::{civil::Date, tz::TimeZone, Timestamp, ToSpan, Zoned};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// A date where any part may be unknown, `may` only knows its month while
+/// `05-14-25.pdf` knows all three.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PartialDate {
+    pub year: Option<i16>,
+    pub month: Option<i8>,
+    pub day: Option<i8>,
+}
+
+impl PartialDate {
+    fn full(d: Date) -> Self {
+        PartialDate {
+            year: Some(d.year()),
+            month: Some(d.month()),
+            day: Some(d.day()),
+        }
+    }
+
+    /// Two dates match if they agree on their month and on every other part they both know.
+    pub fn matches(&self, other: &PartialDate) -> bool {
+        fn agree<T: PartialEq>(a: Option<T>, b: Option<T>) -> bool {
+            match (a, b) {
+                (Some(a), Some(b)) => a == b,
+                _ => true,
+            }
+        }
+
+        self.month.is_some()
+            && self.month == other.month
+            && agree(self.year, other.year)
+            && agree(self.day, other.day)
+    }
+}
+
+const MONTHS: [&str; 12] = [
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+
+/// Parses a date-ish search term: month names or abbreviations (`may`, `sept`),
+/// ISO dates (`2024-07-15`), year and month (`2024-07`), `today` and `yesterday`.
+pub fn parse_date_term(term: &str) -> Option<PartialDate> {
+    let lower = term.to_lowercase();
+
+    match lower.as_str() {
+        "today" => return Some(PartialDate::full(Zoned::now().date())),
+        "yesterday" => {
+            return Zoned::now()
+                .date()
+                .checked_sub(1.day())
+                .ok()
+                .map(PartialDate::full);
+        }
+        _ => {}
+    }
+
+    if let Some(m) = MONTHS
+        .iter()
+        .position(|name| lower.len() >= 3 && name.starts_with(&lower))
+    {
+        return Some(PartialDate {
+            month: Some(m as i8 + 1),
+            ..Default::default()
+        });
+    }
+
+    let parts: Vec<&str> = lower.split('-').collect();
+    let lens: Vec<usize> = parts.iter().map(|p| p.len()).collect();
+    if !parts.iter().all(|p| p.bytes().all(|b| b.is_ascii_digit())) {
+        return None;
+    }
+
+    match lens.as_slice() {
+        [4, 2, 2] => from_parts(parts[0], parts[1], Some(parts[2])),
+        [4, 2] => from_parts(parts[0], parts[1], None),
+        _ => None,
+    }
+}
+
+/// Dots aren't separators, `10.12` and `2.10.12` are versions more often than dates.
+static DIGIT_GROUPS: Lazy<Regex> = Lazy::new(|| Regex::new(r"\d+(?:[-_]\d+)*").unwrap());
+
+/// Finds dates embedded in `s`, in the formats commonly used in file names:
+/// `2024-07-15`, `2024_07_15`, `20250718`, `2024-07`, `05-14-25`, `05-14-2025` and `07-15`.
+pub fn embedded_dates(s: &str) -> Vec<((usize, usize), PartialDate)> {
+    DIGIT_GROUPS
+        .find_iter(s)
+        .filter_map(|m| {
+            let parts: Vec<&str> = m.as_str().split(['-', '_']).collect();
+            let lens: Vec<usize> = parts.iter().map(|p| p.len()).collect();
+
+            let date = match lens.as_slice() {
+                [8] => from_parts(&parts[0][..4], &parts[0][4..6], Some(&parts[0][6..])),
+                [4, 2, 2] => from_parts(parts[0], parts[1], Some(parts[2])),
+                [4, 2] => from_parts(parts[0], parts[1], None),
+                [2, 2, 2] => from_parts(&format!("20{}", parts[2]), parts[0], Some(parts[1])),
+                [2, 2, 4] => from_parts(parts[2], parts[0], Some(parts[1])),
+                [2, 2] => from_month_day(parts[0], parts[1]),
+                _ => None,
+            }?;
+
+            Some(((m.start(), m.end()), date))
+        })
+        .collect()
+}
+
+/// The local date a file was last modified on, from its mtime in seconds.
+pub fn date_of_mtime(secs: i64) -> Option<PartialDate> {
+    let ts = Timestamp::from_second(secs).ok()?;
+    Some(PartialDate::full(ts.to_zoned(TimeZone::system()).date()))
+}
+
+fn from_parts(year: &str, month: &str, day: Option<&str>) -> Option<PartialDate> {
+    let year: i16 = year.parse().ok()?;
+    if !(1900..=2100).contains(&year) {
+        return None;
+    }
+
+    let mut date = from_month_day(month, day.unwrap_or("01"))?;
+    date.year = Some(year);
+    if day.is_none() {
+        date.day = None;
+    }
+
+    Some(date)
+}
+
+fn from_month_day(month: &str, day: &str) -> Option<PartialDate> {
+    let month: i8 = month.parse().ok()?;
+    let day: i8 = day.parse().ok()?;
+
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    Some(PartialDate {
+        year: None,
+        month: Some(month),
+        day: Some(day),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: Option<i16>, month: i8, day: Option<i8>) -> PartialDate {
+        PartialDate {
+            year,
+            month: Some(month),
+            day,
+        }
+    }
+
+    #[test]
+    fn date_terms() {
+        assert_eq!(parse_date_term("May"), Some(date(None, 5, None)));
+        assert_eq!(parse_date_term("September"), Some(date(None, 9, None)));
+        assert_eq!(parse_date_term("sept"), Some(date(None, 9, None)));
+        assert_eq!(parse_date_term("mar"), Some(date(None, 3, None)));
+        assert_eq!(parse_date_term("2024-07"), Some(date(Some(2024), 7, None)));
+        assert_eq!(
+            parse_date_term("2024-07-15"),
+            Some(date(Some(2024), 7, Some(15)))
+        );
+        assert_eq!(parse_date_term("ma"), None);
+        assert_eq!(parse_date_term("2024-13"), None);
+        assert!(parse_date_term("yesterday").is_some());
+    }
+
+    #[test]
+    fn dates_in_file_names() {
+        let found = |s: &str| -> Vec<PartialDate> {
+            embedded_dates(s).into_iter().map(|(_, d)| d).collect()
+        };
+
+        assert_eq!(
+            found("./may/05-14-25.pdf"),
+            vec![date(Some(2025), 5, Some(14))]
+        );
+        assert_eq!(
+            found("/logs/error_log_20250718.log"),
+            vec![date(Some(2025), 7, Some(18))]
+        );
+        assert_eq!(
+            found("/x/2024_07_15.txt"),
+            vec![date(Some(2024), 7, Some(15))]
+        );
+        assert_eq!(
+            found("/docs/meeting_minutes_07-15.pdf"),
+            vec![date(None, 7, Some(15))]
+        );
+        assert!(found("/invoices/invoice_1234.pdf").is_empty());
+        assert!(found("/src/libfoo-10.12.tar.gz").is_empty());
+    }
+
+    #[test]
+    fn partial_dates_match() {
+        let may = date(None, 5, None);
+        assert!(may.matches(&date(Some(2025), 5, Some(14))));
+        assert!(!may.matches(&date(Some(2025), 6, Some(14))));
+        assert!(date(Some(2024), 7, None).matches(&date(None, 7, Some(15))));
+        assert!(!date(Some(2024), 7, None).matches(&date(Some(2025), 7, Some(15))));
+    }
+}
@@ -1 +1,6 @@
 pub mod alias_expansion;
+pub mod dates;
+pub mod normalize;
+pub mod numbers;
+pub mod term;
+pub mod tokens;
@@ -0,0 +1,174 @@
+use std::{borrow::Cow, cell::OnceCell};
+
+use unicode_normalization::{
+    char::is_combining_mark, is_nfc_quick, IsNormalized, UnicodeNormalization,
+};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::options::MatchOptions;
+use crate::parser::tokens::tokenize;
+
+/// Brings `s` into the form used for matching: NFC, optionally without diacritics
+/// and lowercased.
+pub fn normalize<'a>(s: &'a str, opts: &MatchOptions) -> Cow<'a, str> {
+    if is_plain(s, opts) {
+        return if opts.ignore_case {
+            Cow::Owned(s.to_ascii_lowercase())
+        } else {
+            Cow::Borrowed(s)
+        };
+    }
+
+    Cow::Owned(normalize_str(s, opts))
+}
+
+/// Normalizes `s` one grapheme at a time, remembering where every byte of the
+/// result came from so matches can be mapped back onto the original string.
+pub struct Normalized<'a> {
+    pub text: Cow<'a, str>,
+    original: &'a str,
+    /// Byte span of the source grapheme for every byte of `text`, empty when
+    /// `text` has the same offsets as the original.
+    spans: Vec<(usize, usize)>,
+    words: OnceCell<Vec<(usize, usize)>>,
+}
+
+impl<'a> Normalized<'a> {
+    pub fn new(s: &'a str, opts: &MatchOptions) -> Self {
+        if is_plain(s, opts) {
+            return Normalized {
+                text: normalize(s, opts),
+                original: s,
+                spans: vec![],
+                words: OnceCell::new(),
+            };
+        }
+
+        let mut text = String::with_capacity(s.len());
+        let mut spans = Vec::with_capacity(s.len());
+
+        for (start, g) in s.grapheme_indices(true) {
+            let piece = normalize_str(g, opts);
+            spans.extend(std::iter::repeat_n((start, start + g.len()), piece.len()));
+            text.push_str(&piece);
+        }
+
+        Normalized {
+            text: Cow::Owned(text),
+            original: s,
+            spans,
+            words: OnceCell::new(),
+        }
+    }
+
+    /// Word tokens of `text`, see [`tokenize`]. They are found in the original string,
+    /// lowercasing would lose the boundaries inside `meetingMinutes`.
+    pub fn words(&self) -> &[(usize, usize)] {
+        self.words.get_or_init(|| {
+            let tokens = tokenize(self.original);
+            if self.spans.is_empty() {
+                return tokens;
+            }
+
+            tokens
+                .into_iter()
+                .map(|(start, end)| {
+                    (
+                        self.spans.partition_point(|s| s.1 <= start),
+                        self.spans.partition_point(|s| s.0 < end),
+                    )
+                })
+                .filter(|(start, end)| start < end)
+                .collect()
+        })
+    }
+
+    /// Maps a byte range of `text` onto the original string, widened to whole graphemes.
+    pub fn original_range(&self, (start, end): (usize, usize)) -> (usize, usize) {
+        if self.spans.is_empty() || start >= end {
+            return (start, end);
+        }
+
+        (self.spans[start].0, self.spans[end - 1].1)
+    }
+}
+
+/// Text matched as it is, for terms and tests that don't need normalizing.
+impl<'a> From<&'a str> for Normalized<'a> {
+    fn from(s: &'a str) -> Self {
+        Normalized {
+            text: Cow::Borrowed(s),
+            original: s,
+            spans: vec![],
+            words: OnceCell::new(),
+        }
+    }
+}
+
+/// ASCII text is already NFC and has nothing to fold, so offsets never move.
+fn is_plain(s: &str, opts: &MatchOptions) -> bool {
+    s.is_ascii()
+        || (!opts.fold && !opts.ignore_case && is_nfc_quick(s.chars()) == IsNormalized::Yes)
+}
+
+fn normalize_str(s: &str, opts: &MatchOptions) -> String {
+    let folded: String = if opts.fold {
+        s.nfd().filter(|c| !is_combining_mark(*c)).nfc().collect()
+    } else {
+        s.nfc().collect()
+    };
+
+    if opts.ignore_case {
+        folded.to_lowercase()
+    } else {
+        folded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn opts(fold: bool, ignore_case: bool) -> MatchOptions {
+        MatchOptions {
+            fold,
+            ignore_case,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn nfd_matches_nfc() {
+        let nfd = "re\u{301}sume\u{301}.pdf";
+        let n = Normalized::new(nfd, &opts(false, false));
+        assert_eq!(n.text, "r\u{e9}sum\u{e9}.pdf");
+        assert_eq!(n.original_range((1, 3)), (1, 4));
+    }
+
+    #[test]
+    fn folding_strips_diacritics() {
+        let s = "./R\u{e9}sum\u{e9}.pdf";
+        assert_eq!(normalize(s, &opts(true, true)), "./resume.pdf");
+
+        let n = Normalized::new(s, &opts(true, true));
+        let start = n.text.find("resume").unwrap();
+        let (a, b) = n.original_range((start, start + 6));
+        assert_eq!(&s[a..b], "R\u{e9}sum\u{e9}");
+    }
+
+    #[test]
+    fn words_survive_lowercasing() {
+        let words = |n: &Normalized| -> Vec<String> {
+            n.words()
+                .iter()
+                .map(|&(a, b)| n.text[a..b].to_string())
+                .collect()
+        };
+
+        let n = Normalized::new("./meetingMinutes.pdf", &opts(false, true));
+        assert_eq!(words(&n), vec!["meeting", "minutes", "pdf"]);
+
+        let n = Normalized::new("./Caf\u{e9}Cr\u{e8}me.txt", &opts(true, true));
+        assert_eq!(words(&n), vec!["cafe", "creme", "txt"]);
+    }
+}
@@ -0,0 +1,78 @@
+/// Finds every run of ASCII digits in `s`, with its value.
+///
+/// Runs too long to fit a `u64` are skipped.
+pub fn numeric_tokens(s: &str) -> Vec<((usize, usize), u64)> {
+    let bytes = s.as_bytes();
+    let mut tokens = vec![];
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if !bytes[i].is_ascii_digit() {
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        while i < bytes.len() && bytes[i].is_ascii_digit() {
+            i += 1;
+        }
+
+        if let Ok(value) = s[start..i].parse() {
+            tokens.push(((start, i), value));
+        }
+    }
+
+    tokens
+}
+
+/// Parses a number term, only made of digits (`14`, `05`).
+pub fn parse_number(term: &str) -> Option<u64> {
+    if term.is_empty() || !term.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    term.parse().ok()
+}
+
+/// Parses an inclusive range term like `2023..2025`, either bound may be left out.
+pub fn parse_range(term: &str) -> Option<(u64, u64)> {
+    let (lo, hi) = term.split_once("..")?;
+    if lo.is_empty() && hi.is_empty() {
+        return None;
+    }
+
+    let lo = if lo.is_empty() { 0 } else { parse_number(lo)? };
+    let hi = if hi.is_empty() {
+        u64::MAX
+    } else {
+        parse_number(hi)?
+    };
+
+    Some((lo.min(hi), lo.max(hi)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_numeric_tokens() {
+        let values: Vec<u64> = numeric_tokens("./may/05-14-25.pdf")
+            .into_iter()
+            .map(|(_, v)| v)
+            .collect();
+        assert_eq!(values, vec![5, 14, 25]);
+        assert_eq!(numeric_tokens("budget_2024.xlsx"), vec![((7, 11), 2024)]);
+    }
+
+    #[test]
+    fn ranges() {
+        assert_eq!(parse_range("2023..2025"), Some((2023, 2025)));
+        assert_eq!(parse_range("2025..2023"), Some((2023, 2025)));
+        assert_eq!(parse_range("2023.."), Some((2023, u64::MAX)));
+        assert_eq!(parse_range(".."), None);
+        assert_eq!(parse_range("a..b"), None);
+        assert_eq!(parse_number("05"), Some(5));
+        assert_eq!(parse_number("5a"), None);
+    }
+}
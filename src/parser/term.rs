@@ -0,0 +1,423 @@
+use std::fmt;
+
+use globset::{Glob, GlobBuilder, GlobMatcher};
+use regex::{Regex, RegexBuilder};
+
+use crate::cli::error::BFFError;
+use crate::options::MatchOptions;
+use crate::parser::{
+    dates::{date_of_mtime, embedded_dates, parse_date_term, PartialDate},
+    normalize::{normalize, Normalized},
+    numbers::{numeric_tokens, parse_number, parse_range},
+    tokens::initialism_ranges,
+};
+
+/// A single search term, matched against every path in the file tree.
+#[derive(Debug, Clone)]
+pub enum Term {
+    /// Plain substring, the default for every keyword.
+    Fuzzy(String),
+    /// Substring that can also match the initials of consecutive words, used with `--words`.
+    Word(String),
+    /// Date-ish keyword (`may`, `2024-07`, `yesterday`), also matches dates embedded in
+    /// file names and, with `--mtime`, the modification date of the file.
+    Date(String, PartialDate),
+    /// Number (`14`, `05`), matches whole numeric tokens of the same value, so `5` matches
+    /// `05` but not `25`.
+    Number(String, u64),
+    /// Inclusive range of numbers (`2023..2025`), matches any numeric token inside it.
+    Range(String, u64, u64),
+    /// Keyword containing `/`, each part must match one of a run of consecutive path
+    /// components (`src/main` matches `./src/main.rs` and `./resources/domain.rs`).
+    Path(Vec<String>),
+    /// Regular expression, written as `/pattern/` or passed with `--regex`.
+    Regex(Regex),
+    /// Shell style glob, passed with `--glob`.
+    Glob(GlobMatcher),
+}
+
+impl Term {
+    pub fn fuzzy(s: &str) -> Self {
+        Term::Fuzzy(s.to_string())
+    }
+
+    pub fn regex(pattern: &str) -> Result<Self, BFFError> {
+        Ok(Term::Regex(Regex::new(pattern)?))
+    }
+
+    pub fn glob(pattern: &str) -> Result<Self, BFFError> {
+        Ok(Term::Glob(Glob::new(pattern)?.compile_matcher()))
+    }
+
+    /// Parses a keyword from the command line.
+    ///
+    /// Keywords wrapped in slashes (`/\d{2}\.pdf$/`) are treated as regular expressions,
+    /// keywords containing a slash span path components, everything else is a plain substring.
+    pub fn parse(raw: &str) -> Result<Self, BFFError> {
+        match raw.strip_prefix('/').and_then(|r| r.strip_suffix('/')) {
+            Some(pattern) if !pattern.is_empty() => Term::regex(pattern),
+            _ if raw.contains('/') && raw.contains(|c| c != '/') => {
+                Ok(Term::Path(raw.split('/').map(str::to_string).collect()))
+            }
+            _ => Ok(Term::fuzzy(raw)),
+        }
+    }
+
+    /// Brings the term into the same form as the paths it is matched against,
+    /// see [`normalize`].
+    pub fn normalized(self, opts: &MatchOptions) -> Result<Self, BFFError> {
+        Ok(match self {
+            Term::Fuzzy(f) => Term::Fuzzy(normalize(&f, opts).into_owned()),
+            Term::Word(w) => Term::Word(normalize(&w, opts).into_owned()),
+            Term::Date(d, date) => Term::Date(normalize(&d, opts).into_owned(), date),
+            t @ (Term::Number(..) | Term::Range(..)) => t,
+            Term::Path(parts) => Term::Path(
+                parts
+                    .iter()
+                    .map(|p| normalize(p, opts).into_owned())
+                    .collect(),
+            ),
+            Term::Regex(r) => {
+                let pattern = normalize_pattern(r.as_str(), opts);
+                Term::Regex(
+                    RegexBuilder::new(&pattern)
+                        .case_insensitive(opts.ignore_case)
+                        .build()?,
+                )
+            }
+            Term::Glob(g) => {
+                let pattern = normalize_pattern(g.glob().glob(), opts);
+                Term::Glob(
+                    GlobBuilder::new(&pattern)
+                        .case_insensitive(opts.ignore_case)
+                        .build()?
+                        .compile_matcher(),
+                )
+            }
+        })
+    }
+
+    /// Substrings every path matched by this term contains, `None` when the term can
+    /// match without containing any fixed text (regexes, numbers, initialisms...).
+    pub fn literals(&self) -> Option<Vec<&str>> {
+        match self {
+            Term::Fuzzy(f) => Some(vec![f]),
+            Term::Path(parts) => Some(parts.iter().map(String::as_str).collect()),
+            _ => None,
+        }
+    }
+
+    pub fn is_match(&self, text: &Normalized) -> bool {
+        let s = &*text.text;
+        match self {
+            Term::Fuzzy(f) => s.contains(f.as_str()),
+            Term::Word(w) => {
+                s.contains(w.as_str()) || !initialism_ranges(s, text.words(), w).is_empty()
+            }
+            Term::Date(d, date) => {
+                s.contains(d.as_str())
+                    || embedded_dates(s)
+                        .iter()
+                        .any(|(_, found)| date.matches(found))
+            }
+            Term::Number(_, value) => numeric_tokens(s).iter().any(|(_, v)| v == value),
+            Term::Range(_, lo, hi) => numeric_tokens(s)
+                .iter()
+                .any(|(_, v)| (lo..=hi).contains(&v)),
+            Term::Path(parts) => !path_ranges(s, parts).is_empty(),
+            Term::Regex(r) => r.is_match(s),
+            Term::Glob(_) => self.glob_range(s).is_some(),
+        }
+    }
+
+    /// Returns the byte ranges of `text` matched by this term.
+    pub fn ranges(&self, text: &Normalized) -> Vec<(usize, usize)> {
+        let s = &*text.text;
+        match self {
+            Term::Fuzzy(f) => substr_ranges(s, f),
+            Term::Word(w) => {
+                let mut ranges = substr_ranges(s, w);
+                ranges.extend(initialism_ranges(s, text.words(), w));
+                ranges
+            }
+            Term::Date(d, date) => {
+                let mut ranges = substr_ranges(s, d);
+                ranges.extend(
+                    embedded_dates(s)
+                        .into_iter()
+                        .filter(|(_, found)| date.matches(found))
+                        .map(|(r, _)| r),
+                );
+                ranges
+            }
+            Term::Number(_, value) => numeric_tokens(s)
+                .into_iter()
+                .filter(|(_, v)| v == value)
+                .map(|(r, _)| r)
+                .collect(),
+            Term::Range(_, lo, hi) => numeric_tokens(s)
+                .into_iter()
+                .filter(|(_, v)| (lo..=hi).contains(&v))
+                .map(|(r, _)| r)
+                .collect(),
+            Term::Path(parts) => path_ranges(s, parts),
+            Term::Regex(r) => r
+                .find_iter(s)
+                .filter(|m| !m.is_empty())
+                .map(|m| (m.start(), m.end()))
+                .collect(),
+            Term::Glob(_) => self.glob_range(s).into_iter().collect(),
+        }
+    }
+
+    /// Whether a file modified at `mtime` (seconds since the epoch) matches a date term.
+    pub fn matches_mtime(&self, mtime: i64) -> bool {
+        match self {
+            Term::Date(_, date) => date_of_mtime(mtime).is_some_and(|m| date.matches(&m)),
+            _ => false,
+        }
+    }
+
+    /// Globs are tried against the file name first, then against every longer
+    /// suffix of the path that starts on a component boundary.
+    fn glob_range(&self, s: &str) -> Option<(usize, usize)> {
+        let Term::Glob(g) = self else {
+            return None;
+        };
+
+        let mut starts: Vec<usize> = s.match_indices('/').map(|(i, _)| i + 1).collect();
+        starts.insert(0, 0);
+
+        starts
+            .into_iter()
+            .rev()
+            .filter(|&start| start < s.len())
+            .find(|&start| g.is_match(&s[start..]))
+            .map(|start| (start, s.len()))
+    }
+}
+
+/// Patterns handle case themselves, lowercasing them would change the meaning of `\D`, `\W`...
+fn normalize_pattern(pattern: &str, opts: &MatchOptions) -> String {
+    let opts = MatchOptions {
+        ignore_case: false,
+        ..opts.clone()
+    };
+    normalize(pattern, &opts).into_owned()
+}
+
+fn substr_ranges(s: &str, needle: &str) -> Vec<(usize, usize)> {
+    s.match_indices(needle)
+        .map(|(i, m)| (i, i + m.len()))
+        .collect()
+}
+
+/// Splits a path into its components, paired with their byte offset.
+pub fn components(s: &str) -> Vec<(usize, &str)> {
+    let mut offset = 0;
+    s.split('/')
+        .map(|c| {
+            let start = offset;
+            offset += c.len() + 1;
+            (start, c)
+        })
+        .collect()
+}
+
+fn path_ranges(s: &str, parts: &[String]) -> Vec<(usize, usize)> {
+    let comps = components(s);
+    if parts.len() > comps.len() {
+        return vec![];
+    }
+
+    let mut ranges = vec![];
+    for window in comps.windows(parts.len()) {
+        if !window
+            .iter()
+            .zip(parts)
+            .all(|((_, c), p)| c.contains(p.as_str()))
+        {
+            continue;
+        }
+
+        for ((offset, c), p) in window.iter().zip(parts) {
+            if p.is_empty() {
+                continue;
+            }
+            ranges.extend(
+                c.match_indices(p.as_str())
+                    .map(|(i, m)| (offset + i, offset + i + m.len())),
+            );
+        }
+    }
+
+    ranges
+}
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Term::Fuzzy(s)
+            | Term::Word(s)
+            | Term::Date(s, _)
+            | Term::Number(s, _)
+            | Term::Range(s, _, _) => write!(f, "{s}"),
+            Term::Path(parts) => write!(f, "{}", parts.join("/")),
+            Term::Regex(r) => write!(f, "/{r}/"),
+            Term::Glob(g) => write!(f, "{}", g.glob()),
+        }
+    }
+}
+
+/// Everything a search looks for: terms matched against paths and, with `--content`,
+/// terms matched against the contents of files.
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    pub terms: Vec<Term>,
+    pub content: Vec<Term>,
+}
+
+impl From<Vec<Term>> for Query {
+    fn from(terms: Vec<Term>) -> Self {
+        Query {
+            terms,
+            content: vec![],
+        }
+    }
+}
+
+/// Picks the kind of a plain keyword, numbers and dates get matched by value.
+fn keyword(f: String, opts: &MatchOptions) -> Term {
+    if let Some(value) = parse_number(&f) {
+        Term::Number(f, value)
+    } else if let Some((lo, hi)) = parse_range(&f) {
+        Term::Range(f, lo, hi)
+    } else if opts.words {
+        Term::Word(f)
+    } else if let Some(date) = parse_date_term(&f) {
+        Term::Date(f, date)
+    } else {
+        Term::Fuzzy(f)
+    }
+}
+
+/// Builds the full query from positional keywords and the `--regex`/`--glob` options.
+pub fn parse_terms(
+    keywords: &[String],
+    regexes: &[String],
+    globs: &[String],
+    opts: &MatchOptions,
+) -> Result<Vec<Term>, BFFError> {
+    let mut terms = keywords
+        .iter()
+        .map(|k| match Term::parse(k)? {
+            Term::Fuzzy(f) => Ok(keyword(f, opts)),
+            t => Ok(t),
+        })
+        .collect::<Result<Vec<Term>, BFFError>>()?;
+
+    for r in regexes {
+        terms.push(Term::regex(r)?);
+    }
+
+    for g in globs {
+        terms.push(Term::glob(g)?);
+    }
+
+    terms.into_iter().map(|t| t.normalized(opts)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slash_delimited_regex() {
+        let term = Term::parse(r"/\d{2}-\d{2}-\d{2}\.pdf$/").unwrap();
+        assert!(matches!(term, Term::Regex(_)));
+        assert_eq!(
+            term.ranges(&"./payment/checkstubs/may/05-14-25.pdf".into()),
+            vec![(25, 37)]
+        );
+        assert!(!term.is_match(&"./payment/05-14-25.pdf.bak".into()));
+    }
+
+    #[test]
+    fn plain_terms_stay_fuzzy() {
+        assert!(matches!(Term::parse("main").unwrap(), Term::Fuzzy(_)));
+        assert!(matches!(Term::parse("/").unwrap(), Term::Fuzzy(_)));
+        assert!(matches!(Term::parse("//").unwrap(), Term::Fuzzy(_)));
+    }
+
+    #[test]
+    fn path_term_spans_components() {
+        let term = Term::parse("src/main").unwrap();
+        assert!(matches!(term, Term::Path(_)));
+        assert_eq!(
+            term.ranges(&"./bff/src/main.rs".into()),
+            vec![(6, 9), (10, 14)]
+        );
+        assert_eq!(
+            term.ranges(&"./my_src/domain.rs".into()),
+            vec![(5, 8), (11, 15)]
+        );
+        assert!(!term.is_match(&"./main/src.rs".into()));
+        assert!(!term.is_match(&"./src/lib/main.rs".into()));
+
+        let dir = Term::parse("docs/").unwrap();
+        assert!(dir.is_match(&"/docs/readme.md".into()));
+        assert!(!dir.is_match(&"/notes/docs.md".into()));
+    }
+
+    #[test]
+    fn number_terms() {
+        let opts = MatchOptions::default();
+        let terms = parse_terms(
+            &["5".to_string(), "2023..2025".to_string()],
+            &[],
+            &[],
+            &opts,
+        )
+        .unwrap();
+        assert!(matches!(terms[0], Term::Number(_, 5)));
+        assert!(matches!(terms[1], Term::Range(_, 2023, 2025)));
+
+        assert_eq!(terms[0].ranges(&"./05-14-25.pdf".into()), vec![(2, 4)]);
+        assert!(!terms[0].is_match(&"./2050.pdf".into()));
+        assert!(!terms[0].is_match(&"./25.pdf".into()));
+        assert_eq!(
+            terms[1].ranges(&"/spreadsheets/budget_2024.xlsx".into()),
+            vec![(21, 25)]
+        );
+        assert!(!terms[1].is_match(&"/logs/error_log_20250718.log".into()));
+    }
+
+    #[test]
+    fn date_terms_still_match_text() {
+        let terms = parse_terms(&["mar".to_string()], &[], &[], &MatchOptions::default()).unwrap();
+        assert!(matches!(terms[0], Term::Date(_, _)));
+        assert!(terms[0].is_match(&"/marketing/plan.pdf".into()));
+        assert!(terms[0].is_match(&"/reports/2024-03-02.pdf".into()));
+
+        let opts = MatchOptions {
+            words: true,
+            ..Default::default()
+        };
+        let terms = parse_terms(&["may".to_string()], &[], &[], &opts).unwrap();
+        assert!(matches!(terms[0], Term::Word(_)));
+    }
+
+    #[test]
+    fn glob_prefers_file_name() {
+        let term = Term::glob("*.tar.gz").unwrap();
+        let path = "/backups/backup_2023.tar.gz";
+        assert_eq!(term.ranges(&path.into()), vec![(9, path.len())]);
+
+        let term = Term::glob("backups/*.zip").unwrap();
+        assert_eq!(
+            term.ranges(&"/backups/archive_backup.zip".into()),
+            vec![(1, 27)]
+        );
+        assert!(!term.is_match(&"/backups/backup_2023.tar.gz".into()));
+    }
+}
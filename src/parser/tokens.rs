@@ -0,0 +1,140 @@
+/// Splits a path into word tokens, returned as byte ranges.
+///
+/// Tokens are separated by any non alphanumeric character (`/`, `_`, `-`, `.`, ...),
+/// by case transitions (`meetingMinutes`, `HTTPServer`) and by digit/letter boundaries
+/// (`log20250718`).
+pub fn tokenize(s: &str) -> Vec<(usize, usize)> {
+    let chars: Vec<(usize, char)> = s.char_indices().collect();
+    let mut tokens = vec![];
+    let mut start: Option<usize> = None;
+
+    for (i, &(offset, c)) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            if let Some(st) = start.take() {
+                tokens.push((st, offset));
+            }
+            continue;
+        }
+
+        if let Some(st) = start {
+            let (_, prev) = chars[i - 1];
+            let next = chars.get(i + 1).map(|&(_, n)| n);
+
+            let boundary = (prev.is_numeric() != c.is_numeric())
+                || (prev.is_lowercase() && c.is_uppercase())
+                || (prev.is_uppercase()
+                    && c.is_uppercase()
+                    && next.is_some_and(char::is_lowercase));
+
+            if boundary {
+                tokens.push((st, offset));
+                start = Some(offset);
+            }
+        } else {
+            start = Some(offset);
+        }
+    }
+
+    if let Some(st) = start {
+        tokens.push((st, s.len()));
+    }
+
+    tokens
+}
+
+/// Finds runs of consecutive `tokens` of `s`, within one path component, whose initials
+/// spell `term`.
+///
+/// Comparison ignores case, so `mm` matches `meetingMinutes` and `meeting_minutes`.
+/// The returned ranges cover the initial of every token in the run.
+pub fn initialism_ranges(s: &str, tokens: &[(usize, usize)], term: &str) -> Vec<(usize, usize)> {
+    let initials: Vec<char> = term.chars().flat_map(char::to_lowercase).collect();
+    if initials.len() < 2 {
+        return vec![];
+    }
+
+    let mut ranges = vec![];
+
+    for run in tokens.windows(initials.len()) {
+        let same_component = run
+            .windows(2)
+            .all(|pair| !s[pair[0].1..pair[1].0].contains('/'));
+
+        let spelled = run.iter().zip(&initials).all(|(&(start, _), i)| {
+            s[start..]
+                .chars()
+                .next()
+                .is_some_and(|c| c.to_lowercase().eq(std::iter::once(*i)))
+        });
+
+        if same_component && spelled {
+            ranges.extend(run.iter().map(|&(start, _)| {
+                let len = s[start..].chars().next().map_or(0, char::len_utf8);
+                (start, start + len)
+            }));
+        }
+    }
+
+    ranges
+}
+
+/// Rates how well `ranges` line up with word `tokens`: a range covering a whole token is
+/// worth 2, one starting on a token is worth 1 and mid-word hits are worth nothing.
+pub fn word_quality(tokens: &[(usize, usize)], ranges: &[(usize, usize)]) -> u32 {
+    ranges
+        .iter()
+        .map(|&(start, end)| {
+            if tokens.iter().any(|&(ts, te)| ts == start && te == end) {
+                2
+            } else if tokens.iter().any(|&(ts, _)| ts == start) {
+                1
+            } else {
+                0
+            }
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(s: &str) -> Vec<&str> {
+        tokenize(s).into_iter().map(|(a, b)| &s[a..b]).collect()
+    }
+
+    #[test]
+    fn splits_on_case_separators_and_digits() {
+        assert_eq!(
+            words("./docs/meetingMinutes0715.pdf"),
+            vec!["docs", "meeting", "Minutes", "0715", "pdf"]
+        );
+        assert_eq!(
+            words("error_log_20250718.log"),
+            vec!["error", "log", "20250718", "log"]
+        );
+        assert_eq!(words("HTTPServer-v2"), vec!["HTTP", "Server", "v", "2"]);
+    }
+
+    #[test]
+    fn initialisms() {
+        let s = "./docs/meetingMinutes0715.pdf";
+        let tokens = tokenize(s);
+        assert_eq!(initialism_ranges(s, &tokens, "mm"), vec![(7, 8), (14, 15)]);
+        assert_eq!(
+            initialism_ranges(s, &tokens, "MM0"),
+            vec![(7, 8), (14, 15), (21, 22)]
+        );
+        assert!(initialism_ranges(s, &tokens, "dm").is_empty());
+        assert!(initialism_ranges(s, &tokens, "m").is_empty());
+    }
+
+    #[test]
+    fn word_hits_beat_mid_word_hits() {
+        let tokens = tokenize("/logs/error_log_20250718.log");
+        assert_eq!(word_quality(&tokens, &[(12, 15)]), 2);
+        assert_eq!(word_quality(&tokens, &[(1, 4)]), 1);
+        assert_eq!(word_quality(&tokens, &[(2, 4)]), 0);
+    }
+}